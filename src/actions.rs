@@ -1,6 +1,8 @@
 use std::env;
+use std::fs;
 use std::path::Path;
 use std::process::Command;
+use std::time::SystemTime;
 
 pub fn open_in_editor(path: &Path) -> Result<(), String> {
     let editor = env::var("EDITOR").unwrap_or_else(|_| "vim".to_string());
@@ -16,3 +18,112 @@ pub fn open_in_editor(path: &Path) -> Result<(), String> {
         Err(format!("Editor exited with status: {}", status))
     }
 }
+
+/// Flip the task list checkbox whose `[` is at byte `offset` of `path`,
+/// leaving every other byte untouched. `rendered` is the file's modification
+/// time when the preview was made; if the file has changed since, or the
+/// checkbox is no longer where it was, nothing is written. Returns whether
+/// the task is now checked.
+pub fn toggle_task(
+    path: &Path,
+    offset: usize,
+    checked: bool,
+    rendered: Option<SystemTime>,
+) -> Result<bool, String> {
+    let changed = || "File changed on disk; reloading, try again".to_string();
+    let modified = fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if rendered != Some(modified) {
+        return Err(changed());
+    }
+
+    let mut bytes =
+        fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    match bytes.get(offset..).and_then(|rest| rest.get(..3)) {
+        Some([b'[', b'x' | b'X', b']']) if checked => {}
+        Some([b'[', b' ', b']']) if !checked => {}
+        _ => return Err(changed()),
+    }
+    bytes[offset + 1] = if checked { b' ' } else { b'x' };
+
+    fs::write(path, &bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(!checked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::path::PathBuf;
+    use std::time::Duration;
+
+    /// A file in the temp directory holding `content`, removed on drop.
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, content: &str) -> Self {
+            let path =
+                env::temp_dir().join(format!("md-explorer-{}-{}.md", std::process::id(), name));
+            fs::write(&path, content).unwrap();
+            Self(path)
+        }
+
+        fn mtime(&self) -> Option<SystemTime> {
+            fs::metadata(&self.0).and_then(|m| m.modified()).ok()
+        }
+
+        fn read(&self) -> String {
+            fs::read_to_string(&self.0).unwrap()
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn checks_and_unchecks_in_place() {
+        let file = TempFile::new("toggle", "# Tasks\n\n- [ ] one\n- [X] two\n");
+        assert_eq!(toggle_task(&file.0, 11, false, file.mtime()), Ok(true));
+        assert_eq!(file.read(), "# Tasks\n\n- [x] one\n- [X] two\n");
+        assert_eq!(toggle_task(&file.0, 21, true, file.mtime()), Ok(false));
+        assert_eq!(file.read(), "# Tasks\n\n- [x] one\n- [ ] two\n");
+    }
+
+    #[test]
+    fn keeps_crlf_line_endings() {
+        let file = TempFile::new("crlf", "- [ ] one\r\n- [ ] two\r\n");
+        assert_eq!(toggle_task(&file.0, 13, false, file.mtime()), Ok(true));
+        assert_eq!(file.read(), "- [ ] one\r\n- [x] two\r\n");
+    }
+
+    #[test]
+    fn refuses_after_a_concurrent_edit() {
+        let file = TempFile::new("edited", "- [ ] one\n");
+        let rendered = file.mtime();
+        let edited = rendered.unwrap() + Duration::from_secs(5);
+        File::options()
+            .write(true)
+            .open(&file.0)
+            .unwrap()
+            .set_modified(edited)
+            .unwrap();
+        assert!(toggle_task(&file.0, 2, false, rendered).is_err());
+        assert!(toggle_task(&file.0, 2, false, None).is_err());
+        assert_eq!(file.read(), "- [ ] one\n");
+    }
+
+    #[test]
+    fn refuses_a_stale_offset() {
+        let file = TempFile::new("offset", "- [ ] one\n");
+        // Not on a checkbox, past the end, and the wrong state
+        assert!(toggle_task(&file.0, 0, false, file.mtime()).is_err());
+        assert!(toggle_task(&file.0, 9, false, file.mtime()).is_err());
+        assert!(toggle_task(&file.0, usize::MAX - 1, false, file.mtime()).is_err());
+        assert!(toggle_task(&file.0, 2, true, file.mtime()).is_err());
+        assert_eq!(file.read(), "- [ ] one\n");
+    }
+}
@@ -1,31 +1,216 @@
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
+use crate::actions;
+use crate::fs::filter::MatchMode;
+use crate::fs::history::SearchHistory;
+use crate::fs::index::{update_in_background, SearchIndex};
 use crate::fs::scanner::FileTree;
+use crate::markdown::cache::RenderCache;
+use crate::markdown::graph::{self, Backlink, LinkGraph};
+use crate::markdown::headings::{index_in_background, HeadingEntry};
+use crate::markdown::image::{load_image, GraphicsImage, ImageSettings};
+use crate::markdown::links::{
+    find_anchor, resolve_link, resolve_wikilink, LinkRef, LinkTarget, WikiIndex,
+};
+use crate::markdown::loader::{PreviewLoader, PreviewOptions};
+use crate::markdown::render::{render_markdown, RenderedDocument};
+use crate::markdown::slides::{split_slides, Slide};
+use crate::markdown::source::render_source;
+use crate::markdown::todos::{TodoCache, TodoFilter, TodoGroup, TodoItem};
+use crate::navigation::{NavEntry, NavHistory};
+
+/// How often the link graph is re-checked while backlinks are shown.
+const LINK_GRAPH_RECHECK: Duration = Duration::from_secs(2);
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Focus {
     Tree,
     Preview,
     Search,
+    Backlinks,
+    Outline,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Mode {
     Normal,
     Search,
+    PreviewSearch,
+    HeadingSearch,
+    Todos,
+    Slides,
     Help,
 }
 
+/// State of a Ctrl-R reverse incremental search over the search history.
+#[derive(Debug, Clone, Default)]
+pub struct ReverseSearch {
+    pub query: String,
+    pub match_index: Option<usize>,
+    /// Query to restore if the reverse search is cancelled
+    pub original: String,
+}
+
+/// A heading of the previewed document and the wrapped row it renders at.
+#[derive(Debug, Clone)]
+pub struct OutlineEntry {
+    pub level: u8,
+    pub text: String,
+    pub row: u16,
+}
+
+/// A graphics protocol image and the screen cell to draw it at, written to
+/// the terminal after each frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImagePlacement {
+    pub x: u16,
+    pub y: u16,
+    pub graphics: Arc<GraphicsImage>,
+}
+
+/// A task list checkbox in the preview, updated by render.
+#[derive(Debug, Clone)]
+pub struct PreviewTask {
+    pub row: u16,
+    /// Byte offset of the checkbox's `[` in the source file
+    pub offset: usize,
+    pub checked: bool,
+}
+
+/// A task or TODO marker together with the file it belongs to, for the
+/// TODO dashboard.
+#[derive(Debug, Clone)]
+pub struct TodoEntry {
+    pub path: PathBuf,
+    /// The file's frontmatter `owner`
+    pub owner: Option<String>,
+    pub item: TodoItem,
+}
+
+/// What a TODO dashboard section is headed by.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum TodoSectionKey {
+    /// A file or directory
+    Path(PathBuf),
+    Owner(String),
+    NoOwner,
+}
+
+/// Dashboard items sharing a file, directory or owner.
+#[derive(Debug, Clone)]
+pub struct TodoSection {
+    pub key: TodoSectionKey,
+    /// Indices into `todo_index`, in file and line order
+    pub items: Vec<usize>,
+}
+
 pub struct App {
     pub file_tree: FileTree,
     pub selected_index: usize,
     pub tree_scroll: usize,
     pub tree_height: usize,
     pub preview_scroll: u16,
+    /// Rows of the preview pane, updated by render
+    pub preview_height: usize,
     pub focus: Focus,
     pub mode: Mode,
+    /// First line of the help popup in view, clamped by render
+    pub help_scroll: u16,
     pub search_query: String,
+    pub search_match_mode: MatchMode,
+    pub search_error: Option<String>,
+    /// Whether frontmatter has been brought up to date for the `key:value`
+    /// query being typed, so later keystrokes reuse it
+    fields_loaded: bool,
+    pub search_history: SearchHistory,
+    pub reverse_search: Option<ReverseSearch>,
+    /// Where the tree search started, recorded as a jump if a hit is accepted
+    pub search_origin: Option<NavEntry>,
+    /// Loaded in the background on first use of content search
+    pub search_index: Option<SearchIndex>,
+    /// The index being loaded or updated in the background
+    pub search_index_loading: Option<Receiver<SearchIndex>>,
+    /// Score of each content search hit, shown next to it in the tree
+    pub content_scores: HashMap<PathBuf, f64>,
     pub filtered_indices: Vec<usize>,
+    pub preview_query: String,
+    /// Wrapped row of every match of `preview_query`, updated by render
+    pub preview_match_rows: Vec<u16>,
+    pub preview_match_current: usize,
+    /// A match of the current preview search has been jumped to, so later
+    /// jumps are not recorded in the navigation history
+    pub preview_match_jumped: bool,
+    /// Source line to bring to the top of the preview on the next render
+    pub preview_target_line: Option<usize>,
+    /// Rendered previews, so unchanged files are not re-read on every draw
+    pub preview_cache: RenderCache,
+    pub preview_loader: PreviewLoader,
+    /// Show the literal markdown with line numbers instead of rendering it
+    pub raw_view: bool,
+    /// Source line at the top of the preview, updated by render
+    pub preview_source_line: usize,
+    pub show_images: bool,
+    pub image_settings: ImageSettings,
+    /// Images fully in view in the preview, updated by render
+    pub image_placements: Vec<ImagePlacement>,
+    /// Images currently written to the terminal; emptied when the screen is
+    /// cleared
+    pub images_on_screen: Vec<ImagePlacement>,
+    /// Links of the previewed document, updated by render
+    pub preview_links: Vec<LinkRef>,
+    /// Task list checkboxes of the previewed document, updated by render
+    pub preview_tasks: Vec<PreviewTask>,
+    /// File and index into `preview_tasks` of the task cursor
+    pub task_cursor: Option<(PathBuf, usize)>,
+    /// Digits typed so far to pick a link hint
+    pub link_hint_input: String,
+    pub nav_history: NavHistory,
+    /// Scanned markdown files by name for wikilinks, built on first use
+    pub wiki_index: Option<Arc<WikiIndex>>,
+    /// Links between scanned files, built when the backlinks panel is first shown
+    pub link_graph: Option<Arc<LinkGraph>>,
+    /// Receives the link graph while it is updated in the background
+    link_graph_loading: Option<Receiver<Option<Arc<LinkGraph>>>>,
+    /// When the link graph was last brought up to date
+    link_graph_checked: Option<Instant>,
+    pub show_backlinks: bool,
+    pub backlinks_selected: usize,
+    pub show_outline: bool,
+    /// Headings of the previewed document, updated by render while the outline is shown
+    pub outline: Vec<OutlineEntry>,
+    /// Outline cursor; `None` follows the section in view
+    pub outline_selected: Option<usize>,
+    /// Every heading in the scanned files, collected on first use of heading search
+    pub heading_index: Option<Vec<HeadingEntry>>,
+    /// Receives the heading index while it is built in the background
+    pub heading_index_loading: Option<Receiver<Vec<HeadingEntry>>>,
+    pub heading_query: String,
+    /// Indices into `heading_index`, best match first
+    pub heading_matches: Vec<usize>,
+    pub heading_selected: usize,
+    /// Tasks and TODO markers in the scanned files, read when the dashboard opens
+    pub todo_index: Vec<TodoEntry>,
+    todo_cache: TodoCache,
+    pub todo_group: TodoGroup,
+    pub todo_filter: TodoFilter,
+    /// Entries of `todo_index` passing the filter, grouped for display
+    pub todo_sections: Vec<TodoSection>,
+    /// Dashboard cursor, counting items across all sections
+    pub todo_selected: usize,
+    /// File being presented, its slides and when it was last split
+    pub slides_path: Option<PathBuf>,
+    pub slides: Vec<Slide>,
+    slides_mtime: Option<SystemTime>,
+    pub slide_index: usize,
+    pub slide_scroll: u16,
+    pub show_slide_notes: bool,
+    /// The current slide rendered at a width with the view options, so it
+    /// is not re-rendered on every draw
+    pub slide_cache: Option<(usize, u16, PreviewOptions, Arc<RenderedDocument>)>,
     pub should_quit: bool,
     pub status_message: Option<String>,
 }
@@ -34,6 +219,7 @@ impl App {
     pub fn new(file_tree: FileTree) -> Self {
         let total_items = file_tree.flat_list().len();
         let filtered_indices: Vec<usize> = (0..total_items).collect();
+        let (image_settings, show_images) = ImageSettings::detect();
 
         Self {
             file_tree,
@@ -41,10 +227,66 @@ impl App {
             tree_scroll: 0,
             tree_height: 20, // Will be updated by render
             preview_scroll: 0,
+            preview_height: 20, // Will be updated by render
             focus: Focus::Tree,
             mode: Mode::Normal,
+            help_scroll: 0,
             search_query: String::new(),
+            search_match_mode: MatchMode::Fuzzy,
+            search_error: None,
+            fields_loaded: false,
+            search_history: SearchHistory::load(),
+            reverse_search: None,
+            search_origin: None,
+            search_index: None,
+            search_index_loading: None,
+            content_scores: HashMap::new(),
             filtered_indices,
+            preview_query: String::new(),
+            preview_match_rows: Vec::new(),
+            preview_match_current: 0,
+            preview_match_jumped: false,
+            preview_target_line: None,
+            preview_cache: RenderCache::default(),
+            preview_loader: PreviewLoader::new(),
+            raw_view: false,
+            preview_source_line: 0,
+            show_images,
+            image_settings,
+            image_placements: Vec::new(),
+            images_on_screen: Vec::new(),
+            preview_links: Vec::new(),
+            preview_tasks: Vec::new(),
+            task_cursor: None,
+            link_hint_input: String::new(),
+            nav_history: NavHistory::default(),
+            wiki_index: None,
+            link_graph: None,
+            link_graph_loading: None,
+            link_graph_checked: None,
+            show_backlinks: false,
+            backlinks_selected: 0,
+            show_outline: false,
+            outline: Vec::new(),
+            outline_selected: None,
+            heading_index: None,
+            heading_index_loading: None,
+            heading_query: String::new(),
+            heading_matches: Vec::new(),
+            heading_selected: 0,
+            todo_index: Vec::new(),
+            todo_cache: TodoCache::default(),
+            todo_group: TodoGroup::File,
+            todo_filter: TodoFilter::Open,
+            todo_sections: Vec::new(),
+            todo_selected: 0,
+            slides_path: None,
+            slides: Vec::new(),
+            slides_mtime: None,
+            slide_index: 0,
+            slide_scroll: 0,
+            show_slide_notes: false,
+            slide_cache: None,
             should_quit: false,
             status_message: None,
         }
@@ -124,7 +366,7 @@ impl App {
         self.focus = match self.focus {
             Focus::Tree => Focus::Preview,
             Focus::Preview => Focus::Tree,
-            Focus::Search => Focus::Tree,
+            Focus::Search | Focus::Backlinks | Focus::Outline => Focus::Tree,
         };
     }
 
@@ -132,11 +374,55 @@ impl App {
         self.mode = Mode::Search;
         self.focus = Focus::Search;
         self.search_query.clear();
+        self.fields_loaded = false;
+        self.search_origin = self.current_location();
     }
 
     pub fn exit_search_mode(&mut self) {
         self.mode = Mode::Normal;
         self.focus = Focus::Tree;
+        self.reverse_search = None;
+        self.search_origin = None;
+        self.search_history.reset_cursor();
+    }
+
+    /// Accept the current query, recording it in the search history, and
+    /// the file the search started from as a jump if the selection moved.
+    pub fn accept_search(&mut self) {
+        let origin = self.search_origin.take();
+        self.search_history.push(&self.search_query);
+        // Saved right away so a crash does not lose the session's searches
+        self.search_history.save();
+        self.exit_search_mode();
+        // Land on a hit rather than on the directories above it
+        let first_file = || {
+            let flat = self.file_tree.flat_list();
+            self.filtered_indices
+                .iter()
+                .position(|&idx| flat[idx].0.is_file())
+        };
+        self.selected_index = if self.search_query.is_empty() {
+            0
+        } else {
+            self.best_content_hit().or_else(first_file).unwrap_or(0)
+        };
+        if let Some(origin) = origin {
+            if self.selected_file() != Some(&origin.path) {
+                self.nav_history.record(origin);
+            }
+        }
+    }
+
+    /// Position in `filtered_indices` of the highest scoring content search
+    /// hit, if content search has results.
+    fn best_content_hit(&self) -> Option<usize> {
+        let flat = self.file_tree.flat_list();
+        self.filtered_indices
+            .iter()
+            .enumerate()
+            .filter_map(|(pos, &idx)| Some((pos, *self.content_scores.get(&flat[idx].0)?)))
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))
+            .map(|(pos, _)| pos)
     }
 
     pub fn clear_search(&mut self) {
@@ -146,41 +432,1125 @@ impl App {
     }
 
     pub fn update_filter(&mut self) {
-        use crate::fs::filter::fuzzy_filter;
+        use crate::fs::filter::{filter_by_paths, filter_items, split_field_terms};
 
-        let flat = self.file_tree.flat_list();
-        if self.search_query.is_empty() {
-            self.filtered_indices = (0..flat.len()).collect();
+        // `key:value` terms for keys found in some frontmatter match it;
+        // regexes keep their colons
+        let (query, fields) =
+            if self.search_match_mode == MatchMode::Regex || !self.search_query.contains(':') {
+                // The next field query re-reads files changed since this one
+                self.fields_loaded = false;
+                (self.search_query.clone(), Vec::new())
+            } else {
+                if !self.fields_loaded {
+                    self.file_tree.load_frontmatter();
+                    self.fields_loaded = true;
+                }
+                let file_tree = &self.file_tree;
+                split_field_terms(&self.search_query, |key| file_tree.has_frontmatter_key(key))
+            };
+
+        self.content_scores.clear();
+        if self.search_match_mode == MatchMode::Content && !query.is_empty() {
+            // The tree keeps its shape, so the ranking is shown as scores
+            // and the best hit is selected
+            let hits = self
+                .search_index()
+                .map(|index| index.search(&query, usize::MAX))
+                .unwrap_or_default();
+            let paths: Vec<PathBuf> = hits.iter().map(|hit| hit.path.clone()).collect();
+            self.content_scores = hits.into_iter().map(|hit| (hit.path, hit.score)).collect();
+            self.filtered_indices = filter_by_paths(self.file_tree.flat_list(), &paths);
+            self.search_error = None;
+            self.selected_index = self.best_content_hit().unwrap_or(0);
         } else {
-            self.filtered_indices = fuzzy_filter(flat, &self.search_query);
+            let flat = self.file_tree.flat_list();
+            match filter_items(flat, &query, self.search_match_mode) {
+                Ok(indices) => {
+                    self.filtered_indices = indices;
+                    self.search_error = None;
+                }
+                // Keep the previous results visible while the pattern is invalid
+                Err(e) => {
+                    self.filtered_indices.retain(|&idx| idx < flat.len());
+                    self.search_error = Some(e);
+                }
+            }
+        }
+
+        if !fields.is_empty() {
+            let flat = self.file_tree.flat_list();
+            let files: Vec<PathBuf> = self
+                .filtered_indices
+                .iter()
+                .map(|&idx| &flat[idx].0)
+                .filter(|path| {
+                    self.file_tree
+                        .frontmatter(path)
+                        .is_some_and(|fm| fields.iter().all(|(key, value)| fm.matches(key, value)))
+                })
+                .cloned()
+                .collect();
+            self.filtered_indices = filter_by_paths(flat, &files);
         }
 
-        if !self.filtered_indices.is_empty() && self.selected_index >= self.filtered_indices.len() {
+        if self.selected_index >= self.filtered_indices.len() {
             self.selected_index = 0;
         }
     }
 
+    pub fn cycle_sort_order(&mut self) {
+        let selected = self.selected_file().cloned();
+        let order = self.file_tree.cycle_sort_order();
+        self.update_filter();
+        if let Some(path) = selected {
+            self.reveal_path(&path);
+        }
+        self.status_message = Some(format!("Sort by {}", order.label()));
+    }
+
     pub fn push_search_char(&mut self, c: char) {
+        self.search_history.reset_cursor();
         self.search_query.push(c);
         self.update_filter();
     }
 
     pub fn pop_search_char(&mut self) {
+        self.search_history.reset_cursor();
         self.search_query.pop();
         self.update_filter();
     }
 
+    pub fn history_previous(&mut self) {
+        if let Some(query) = self.search_history.previous(&self.search_query) {
+            self.search_query = query.to_string();
+            self.update_filter();
+        }
+    }
+
+    pub fn history_next(&mut self) {
+        if let Some(query) = self.search_history.next() {
+            self.search_query = query;
+            self.update_filter();
+        }
+    }
+
+    /// Start a reverse search, or step to the next older match if one is active.
+    pub fn reverse_search_step(&mut self) {
+        match &mut self.reverse_search {
+            None => {
+                self.reverse_search = Some(ReverseSearch {
+                    original: self.search_query.clone(),
+                    ..ReverseSearch::default()
+                });
+            }
+            Some(rs) => {
+                let before = rs.match_index;
+                if let Some(idx) = self.search_history.search_backward(&rs.query, before) {
+                    rs.match_index = Some(idx);
+                    self.apply_reverse_match();
+                }
+            }
+        }
+    }
+
+    pub fn push_reverse_search_char(&mut self, c: char) {
+        if let Some(rs) = &mut self.reverse_search {
+            rs.query.push(c);
+            self.refresh_reverse_match();
+        }
+    }
+
+    pub fn pop_reverse_search_char(&mut self) {
+        if let Some(rs) = &mut self.reverse_search {
+            rs.query.pop();
+            self.refresh_reverse_match();
+        }
+    }
+
+    /// Search again from the newest entry after the reverse query changed.
+    fn refresh_reverse_match(&mut self) {
+        if let Some(rs) = &mut self.reverse_search {
+            rs.match_index = self.search_history.search_backward(&rs.query, None);
+            self.apply_reverse_match();
+        }
+    }
+
+    fn apply_reverse_match(&mut self) {
+        let matched = self
+            .reverse_search
+            .as_ref()
+            .and_then(|rs| rs.match_index)
+            .and_then(|idx| self.search_history.get(idx))
+            .map(str::to_string);
+        if let Some(query) = matched {
+            self.search_query = query;
+            self.update_filter();
+        }
+    }
+
+    /// Keep the matched query and leave reverse search.
+    pub fn accept_reverse_search(&mut self) {
+        self.reverse_search = None;
+    }
+
+    /// Restore the query from before the reverse search started.
+    pub fn cancel_reverse_search(&mut self) {
+        if let Some(rs) = self.reverse_search.take() {
+            self.search_query = rs.original;
+            self.update_filter();
+        }
+    }
+
+    pub fn cycle_match_mode(&mut self) {
+        self.search_match_mode = self.search_match_mode.next();
+        self.update_filter();
+    }
+
+    pub fn enter_preview_search(&mut self) {
+        self.mode = Mode::PreviewSearch;
+        self.focus = Focus::Preview;
+        self.preview_query.clear();
+        self.preview_match_rows.clear();
+        self.preview_match_current = 0;
+        self.preview_match_jumped = false;
+    }
+
+    pub fn push_preview_search_char(&mut self, c: char) {
+        self.preview_query.push(c);
+    }
+
+    pub fn pop_preview_search_char(&mut self) {
+        self.preview_query.pop();
+    }
+
+    /// Leave preview search input and jump to the first match in view or below.
+    pub fn accept_preview_search(&mut self) {
+        self.mode = Mode::Normal;
+        if let Some(idx) = self
+            .preview_match_rows
+            .iter()
+            .position(|&row| row >= self.preview_scroll)
+        {
+            self.jump_to_preview_match(idx);
+        } else if !self.preview_match_rows.is_empty() {
+            self.jump_to_preview_match(0);
+        }
+    }
+
+    pub fn clear_preview_search(&mut self) {
+        self.mode = Mode::Normal;
+        self.preview_query.clear();
+        self.preview_match_rows.clear();
+        self.preview_match_current = 0;
+        self.preview_match_jumped = false;
+    }
+
+    pub fn next_preview_match(&mut self) {
+        let count = self.preview_match_rows.len();
+        if count > 0 {
+            self.jump_to_preview_match((self.preview_match_current + 1) % count);
+        }
+    }
+
+    pub fn prev_preview_match(&mut self) {
+        let count = self.preview_match_rows.len();
+        if count > 0 {
+            self.jump_to_preview_match((self.preview_match_current + count - 1) % count);
+        }
+    }
+
+    /// Scroll to a match. The first jump of a search is recorded so it can
+    /// be undone with [`App::nav_back`].
+    fn jump_to_preview_match(&mut self, idx: usize) {
+        if !std::mem::replace(&mut self.preview_match_jumped, true) {
+            self.record_jump();
+        }
+        self.preview_match_current = idx;
+        self.preview_scroll = self.preview_match_rows[idx];
+    }
+
+    /// The content search index, or `None` while it is loaded and brought
+    /// up to date in the background on first use.
+    pub fn search_index(&mut self) -> Option<&SearchIndex> {
+        if self.search_index.is_none() && self.search_index_loading.is_none() {
+            self.search_index_loading =
+                Some(update_in_background(None, self.file_tree.markdown_files()));
+        }
+        self.search_index.as_ref()
+    }
+
+    /// Whether the content search index is being loaded or updated.
+    pub fn is_indexing(&self) -> bool {
+        self.search_index_loading.is_some()
+    }
+
+    /// Take the search index once the background load finishes, re-running
+    /// a content search waiting on it. Returns true if it arrived.
+    pub fn receive_search_index(&mut self) -> bool {
+        let Some(index) = self
+            .search_index_loading
+            .as_ref()
+            .and_then(|rx| rx.try_recv().ok())
+        else {
+            return false;
+        };
+        self.search_index = Some(index);
+        self.search_index_loading = None;
+        if self.search_match_mode == MatchMode::Content && !self.search_query.is_empty() {
+            self.update_filter();
+        }
+        true
+    }
+
+    /// Take the heading index once its background build has finished.
+    /// Returns whether it arrived.
+    pub fn receive_heading_index(&mut self) -> bool {
+        let Some(entries) = self
+            .heading_index_loading
+            .as_ref()
+            .and_then(|rx| rx.try_recv().ok())
+        else {
+            return false;
+        };
+        self.heading_index = Some(entries);
+        self.heading_index_loading = None;
+        if self.mode == Mode::HeadingSearch {
+            self.update_heading_matches();
+        }
+        true
+    }
+
+    /// The wikilink name index, built from the file tree on first use.
+    pub fn wiki_index(&mut self) -> &Arc<WikiIndex> {
+        let file_tree = &self.file_tree;
+        self.wiki_index
+            .get_or_insert_with(|| Arc::new(WikiIndex::new(&file_tree.markdown_files())))
+    }
+
+    /// The rendered preview of `path` at `width`, or `None` while it is
+    /// first loaded in the background. A file changed on disk keeps its
+    /// previous rendering until the new one arrives, so saving does not flicker.
+    pub fn preview_document(&mut self, path: &Path, width: u16) -> Option<Arc<RenderedDocument>> {
+        let options = self.preview_options();
+        if let Some(document) = self.preview_cache.get(path, width, options) {
+            return Some(document);
+        }
+        let wiki = Arc::clone(self.wiki_index());
+        let roots = self.file_tree.roots.clone();
+        self.preview_loader
+            .request(path, width, options, wiki, roots);
+        self.preview_cache.get_stale(path, width, options)
+    }
+
+    /// Move finished background loads into the preview cache. Returns true
+    /// if anything arrived.
+    pub fn receive_previews(&mut self) -> bool {
+        let mut received = false;
+        while let Some(loaded) = self.preview_loader.try_recv() {
+            self.preview_cache.insert(
+                &loaded.path,
+                loaded.mtime,
+                loaded.width,
+                loaded.options,
+                loaded.document,
+            );
+            received = true;
+        }
+        received
+    }
+
+    pub fn enter_heading_search(&mut self) {
+        if self.heading_index.is_none() && self.heading_index_loading.is_none() {
+            self.heading_index_loading = Some(index_in_background(self.file_tree.markdown_files()));
+        }
+        self.mode = Mode::HeadingSearch;
+        self.heading_query.clear();
+        self.update_heading_matches();
+    }
+
+    pub fn exit_heading_search(&mut self) {
+        self.mode = Mode::Normal;
+    }
+
+    pub fn push_heading_char(&mut self, c: char) {
+        self.heading_query.push(c);
+        self.update_heading_matches();
+    }
+
+    pub fn pop_heading_char(&mut self) {
+        self.heading_query.pop();
+        self.update_heading_matches();
+    }
+
+    fn update_heading_matches(&mut self) {
+        use fuzzy_matcher::skim::SkimMatcherV2;
+        use fuzzy_matcher::FuzzyMatcher;
+
+        let entries = self.heading_index.as_deref().unwrap_or_default();
+        self.heading_selected = 0;
+        if self.heading_query.is_empty() {
+            self.heading_matches = (0..entries.len()).collect();
+            return;
+        }
+
+        let matcher = SkimMatcherV2::default();
+        let mut scored: Vec<(usize, i64)> = entries
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, entry)| {
+                matcher
+                    .fuzzy_match(&entry.heading.text, &self.heading_query)
+                    .map(|score| (idx, score))
+            })
+            .collect();
+        scored.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+        self.heading_matches = scored.into_iter().map(|(idx, _)| idx).collect();
+    }
+
+    pub fn heading_move_up(&mut self) {
+        self.heading_selected = self.heading_selected.saturating_sub(1);
+    }
+
+    pub fn heading_move_down(&mut self) {
+        if self.heading_selected + 1 < self.heading_matches.len() {
+            self.heading_selected += 1;
+        }
+    }
+
+    /// Open the selected heading's file with the preview scrolled to it.
+    pub fn pick_heading(&mut self) {
+        let entry = self
+            .heading_matches
+            .get(self.heading_selected)
+            .and_then(|&idx| self.heading_index.as_ref()?.get(idx))
+            .cloned();
+        self.exit_heading_search();
+
+        let Some(entry) = entry else {
+            return;
+        };
+        if self.jump_to_path(&entry.path) {
+            self.preview_target_line = Some(entry.heading.line);
+            self.focus = Focus::Preview;
+        } else {
+            self.status_message = Some(format!("{} is no longer available", entry.path.display()));
+        }
+    }
+
+    /// Open the TODO dashboard. Files are re-read on every open, since tasks
+    /// get checked off in the preview and in the editor.
+    pub fn enter_todos(&mut self) {
+        self.file_tree.load_frontmatter();
+        let files = self.file_tree.markdown_files();
+        self.todo_cache.retain(&files);
+        let mut index = Vec::new();
+        for path in files {
+            let owner = self
+                .file_tree
+                .frontmatter(&path)
+                .and_then(|fm| fm.get("owner"))
+                .filter(|owner| !owner.is_empty())
+                .map(str::to_string);
+            index.extend(self.todo_cache.items(&path).iter().map(|item| TodoEntry {
+                path: path.clone(),
+                owner: owner.clone(),
+                item: item.clone(),
+            }));
+        }
+        self.todo_index = index;
+        self.mode = Mode::Todos;
+        self.update_todo_sections();
+    }
+
+    pub fn exit_todos(&mut self) {
+        self.mode = Mode::Normal;
+    }
+
+    pub fn cycle_todo_group(&mut self) {
+        self.todo_group = self.todo_group.next();
+        self.update_todo_sections();
+    }
+
+    pub fn cycle_todo_filter(&mut self) {
+        self.todo_filter = self.todo_filter.next();
+        self.update_todo_sections();
+    }
+
+    fn update_todo_sections(&mut self) {
+        let mut sections: BTreeMap<TodoSectionKey, Vec<usize>> = BTreeMap::new();
+        for (idx, entry) in self.todo_index.iter().enumerate() {
+            if !self.todo_filter.matches(entry.item.kind) {
+                continue;
+            }
+            let key = match self.todo_group {
+                TodoGroup::File => TodoSectionKey::Path(entry.path.clone()),
+                TodoGroup::Directory => {
+                    TodoSectionKey::Path(entry.path.parent().unwrap_or(&entry.path).to_path_buf())
+                }
+                TodoGroup::Owner => match &entry.owner {
+                    Some(owner) => TodoSectionKey::Owner(owner.clone()),
+                    None => TodoSectionKey::NoOwner,
+                },
+            };
+            sections.entry(key).or_default().push(idx);
+        }
+        self.todo_sections = sections
+            .into_iter()
+            .map(|(key, items)| TodoSection { key, items })
+            .collect();
+        self.todo_selected = 0;
+    }
+
+    /// Number of items shown in the dashboard.
+    pub fn todo_count(&self) -> usize {
+        self.todo_sections.iter().map(|s| s.items.len()).sum()
+    }
+
+    pub fn todo_move_up(&mut self) {
+        self.todo_selected = self.todo_selected.saturating_sub(1);
+    }
+
+    pub fn todo_move_down(&mut self) {
+        if self.todo_selected + 1 < self.todo_count() {
+            self.todo_selected += 1;
+        }
+    }
+
+    /// Open the selected item's file with the preview scrolled to its line.
+    pub fn pick_todo(&mut self) {
+        let entry = self
+            .todo_sections
+            .iter()
+            .flat_map(|section| &section.items)
+            .nth(self.todo_selected)
+            .map(|&idx| self.todo_index[idx].clone());
+        self.exit_todos();
+
+        let Some(entry) = entry else {
+            return;
+        };
+        if self.jump_to_path(&entry.path) {
+            self.preview_target_line = Some(entry.item.line);
+            self.focus = Focus::Preview;
+        } else {
+            self.status_message = Some(format!("{} is no longer available", entry.path.display()));
+        }
+    }
+
+    /// Present the selected file as slides, starting at the one in view in
+    /// the preview.
+    pub fn enter_slides(&mut self) {
+        let Some(path) = self.selected_file().filter(|p| p.is_file()).cloned() else {
+            self.status_message = Some("Select a markdown file to present".to_string());
+            return;
+        };
+        let mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to read {}: {}", path.display(), e));
+                return;
+            }
+        };
+        self.slides = split_slides(&content);
+        if self.slides.is_empty() {
+            self.status_message = Some("Nothing to present".to_string());
+            return;
+        }
+        self.slides_mtime = mtime;
+        self.slide_index = self
+            .slides
+            .iter()
+            .rposition(|slide| slide.line <= self.preview_source_line)
+            .unwrap_or(0);
+        self.slides_path = Some(path);
+        self.slide_scroll = 0;
+        self.slide_cache = None;
+        self.cancel_link_hint();
+        self.mode = Mode::Slides;
+    }
+
+    pub fn exit_slides(&mut self) {
+        self.mode = Mode::Normal;
+        self.slide_cache = None;
+    }
+
+    /// Re-split the presented file if it changed on disk since it was last
+    /// split, staying on the same slide where it still exists. Returns
+    /// whether the slides were reloaded.
+    pub fn reload_stale_slides(&mut self) -> bool {
+        if self.mode != Mode::Slides {
+            return false;
+        }
+        let Some(path) = &self.slides_path else {
+            return false;
+        };
+        let mtime = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        if mtime == self.slides_mtime {
+            return false;
+        }
+        // Remember the new time even if the file can't be read, so it isn't
+        // retried until it changes again
+        self.slides_mtime = mtime;
+        let slides = match std::fs::read_to_string(path) {
+            Ok(content) => split_slides(&content),
+            Err(_) => return false,
+        };
+        if slides.is_empty() {
+            return false;
+        }
+        self.slides = slides;
+        self.slide_index = self.slide_index.min(self.slides.len() - 1);
+        self.slide_cache = None;
+        true
+    }
+
+    pub fn next_slide(&mut self) {
+        self.go_to_slide((self.slide_index + 1).min(self.slides.len().saturating_sub(1)));
+    }
+
+    pub fn prev_slide(&mut self) {
+        self.go_to_slide(self.slide_index.saturating_sub(1));
+    }
+
+    pub fn first_slide(&mut self) {
+        self.go_to_slide(0);
+    }
+
+    pub fn last_slide(&mut self) {
+        self.go_to_slide(self.slides.len().saturating_sub(1));
+    }
+
+    fn go_to_slide(&mut self, idx: usize) {
+        if idx != self.slide_index {
+            self.slide_index = idx;
+            self.slide_scroll = 0;
+        }
+    }
+
+    pub fn scroll_slide_up(&mut self) {
+        self.slide_scroll = self.slide_scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_slide_down(&mut self) {
+        self.slide_scroll = self.slide_scroll.saturating_add(1);
+    }
+
+    pub fn toggle_slide_notes(&mut self) {
+        self.show_slide_notes = !self.show_slide_notes;
+    }
+
+    /// The current slide rendered at `width`, with images and wikilinks
+    /// resolved against the presented file.
+    pub fn slide_document(&mut self, width: u16) -> Option<Arc<RenderedDocument>> {
+        let options = self.preview_options();
+        if let Some((idx, cached_width, cached_options, document)) = &self.slide_cache {
+            if *idx == self.slide_index && *cached_width == width && *cached_options == options {
+                return Some(Arc::clone(document));
+            }
+        }
+        let path = self.slides_path.clone()?;
+        let wiki = Arc::clone(self.wiki_index());
+        let settings = options.images;
+        let roots = &self.file_tree.roots;
+        let slide = self.slides.get(self.slide_index)?;
+
+        let resolves = |dest: &str| resolve_wikilink(&path, dest, &wiki).is_some();
+        let images = |dest: &str, max_cols: u16| {
+            let settings = settings?;
+            match resolve_link(&path, dest, roots) {
+                LinkTarget::Local {
+                    path: Some(image), ..
+                } => load_image(&image, max_cols, settings).ok(),
+                _ => None,
+            }
+        };
+        let document = Arc::new(if options.raw {
+            render_source(&slide.source, width)
+        } else {
+            render_markdown(&slide.source, width, &resolves, &images)
+        });
+        self.slide_cache = Some((self.slide_index, width, options, Arc::clone(&document)));
+        Some(document)
+    }
+
+    /// Add a digit to the link hint being typed. The link is followed as soon
+    /// as no longer number could match; digits that cannot lead to a link
+    /// are refused.
+    pub fn push_link_digit(&mut self, digit: char) {
+        let count = self.preview_links.len();
+        if count == 0 {
+            self.status_message = Some("No links in this preview".to_string());
+            return;
+        }
+        let input = format!("{}{}", self.link_hint_input, digit);
+        let typed: usize = input.parse().unwrap_or(usize::MAX);
+        if input.starts_with('0') || typed > count {
+            self.status_message = Some(format!("No link [{}]; links are 1-{}", input, count));
+            return;
+        }
+        self.link_hint_input = input;
+        if typed.saturating_mul(10) > count {
+            self.follow_link_hint();
+        }
+    }
+
+    pub fn cancel_link_hint(&mut self) {
+        self.link_hint_input.clear();
+    }
+
+    /// Follow the link whose hint number has been typed. Local markdown
+    /// targets are selected in the tree and scrolled to their anchor;
+    /// external URLs are only shown.
+    pub fn follow_link_hint(&mut self) {
+        let input = std::mem::take(&mut self.link_hint_input);
+        let link = input
+            .parse::<usize>()
+            .ok()
+            .and_then(|n| n.checked_sub(1))
+            .and_then(|idx| self.preview_links.get(idx))
+            .cloned();
+        let Some(link) = link else {
+            self.status_message = Some(format!("No link [{}]", input));
+            return;
+        };
+        let Some(current) = self.selected_file().cloned() else {
+            return;
+        };
+
+        let target = match &link {
+            LinkRef::Markdown(dest) => resolve_link(&current, dest, &self.file_tree.roots),
+            LinkRef::Wiki(dest) => match resolve_wikilink(&current, dest, self.wiki_index()) {
+                Some(target) => target,
+                None => {
+                    self.status_message = Some(format!("No note named [[{}]]", dest));
+                    return;
+                }
+            },
+        };
+
+        match target {
+            LinkTarget::External(url) => {
+                self.status_message = Some(format!("External link (not opened): {}", url));
+            }
+            LinkTarget::Local { path, anchor } => {
+                let path = path.unwrap_or_else(|| current.clone());
+                let origin = self.current_location();
+                if path != current && !self.reveal_path(&path) {
+                    self.status_message = Some(format!("Not in file tree: {}", path.display()));
+                    return;
+                }
+                if let Some(origin) = origin {
+                    self.nav_history.record(origin);
+                }
+                self.focus = Focus::Preview;
+
+                let Some(anchor) = anchor else {
+                    return;
+                };
+                let line = std::fs::read_to_string(&path)
+                    .ok()
+                    .and_then(|content| find_anchor(&content, &anchor));
+                match line {
+                    Some(line) => self.preview_target_line = Some(line),
+                    None => self.status_message = Some(format!("No heading for #{}", anchor)),
+                }
+            }
+        }
+    }
+
+    /// Bring the link graph up to date with the scanned files in the
+    /// background, unless that is already under way.
+    fn update_link_graph(&mut self) {
+        if self.link_graph_loading.is_some() {
+            return;
+        }
+        let files = self.file_tree.markdown_files();
+        let roots = self.file_tree.roots.clone();
+        let wiki = Arc::clone(self.wiki_index());
+        self.link_graph_loading = Some(graph::update_in_background(
+            self.link_graph.clone(),
+            files,
+            roots,
+            wiki,
+        ));
+        self.link_graph_checked = Some(Instant::now());
+    }
+
+    /// While backlinks are shown, re-check the link graph every
+    /// `LINK_GRAPH_RECHECK`, so edits made outside the app show up.
+    pub fn recheck_link_graph(&mut self) {
+        let due = self
+            .link_graph_checked
+            .is_none_or(|checked| checked.elapsed() >= LINK_GRAPH_RECHECK);
+        if self.show_backlinks && due {
+            self.update_link_graph();
+        }
+    }
+
+    /// Take the link graph once a background update has finished. Returns
+    /// whether it changed.
+    pub fn receive_link_graph(&mut self) -> bool {
+        let Some(graph) = self
+            .link_graph_loading
+            .as_ref()
+            .and_then(|rx| rx.try_recv().ok())
+        else {
+            return false;
+        };
+        self.link_graph_loading = None;
+        let Some(graph) = graph else {
+            return false;
+        };
+        self.link_graph = Some(graph);
+        true
+    }
+
+    /// Show or hide the backlinks panel, focusing it when shown.
+    pub fn toggle_backlinks(&mut self) {
+        self.show_backlinks = !self.show_backlinks;
+        if self.show_backlinks {
+            self.update_link_graph();
+            self.backlinks_selected = 0;
+            self.focus = Focus::Backlinks;
+        } else if self.focus == Focus::Backlinks {
+            self.focus = Focus::Preview;
+        }
+    }
+
+    /// Whether the link graph is being built for the first time.
+    pub fn backlinks_loading(&self) -> bool {
+        self.link_graph.is_none() && self.link_graph_loading.is_some()
+    }
+
+    /// Documents linking to the selected file.
+    pub fn selected_backlinks(&self) -> &[Backlink] {
+        match (&self.link_graph, self.selected_file()) {
+            (Some(graph), Some(path)) => graph.backlinks(path),
+            _ => &[],
+        }
+    }
+
+    pub fn backlinks_move_up(&mut self) {
+        self.backlinks_selected = self.backlinks_selected.saturating_sub(1);
+    }
+
+    pub fn backlinks_move_down(&mut self) {
+        let count = self.selected_backlinks().len();
+        if self.backlinks_selected + 1 < count {
+            self.backlinks_selected += 1;
+        }
+    }
+
+    /// Open the referring file at the line of the selected backlink.
+    pub fn open_backlink(&mut self) {
+        let backlinks = self.selected_backlinks();
+        let Some(backlink) = backlinks
+            .get(
+                self.backlinks_selected
+                    .min(backlinks.len().saturating_sub(1)),
+            )
+            .cloned()
+        else {
+            return;
+        };
+        if self.jump_to_path(&backlink.source) {
+            self.preview_target_line = Some(backlink.line);
+            self.backlinks_selected = 0;
+            self.focus = Focus::Preview;
+        } else {
+            self.status_message = Some(format!("Not in file tree: {}", backlink.source.display()));
+        }
+    }
+
+    /// Show or hide the outline panel, focusing it when shown.
+    pub fn toggle_outline(&mut self) {
+        self.show_outline = !self.show_outline;
+        self.outline_selected = None;
+        if self.show_outline {
+            self.focus = Focus::Outline;
+        } else if self.focus == Focus::Outline {
+            self.focus = Focus::Preview;
+        }
+    }
+
+    /// The outline entry for the section at the top of the preview.
+    pub fn current_section(&self) -> Option<usize> {
+        self.outline
+            .iter()
+            .rposition(|entry| entry.row <= self.preview_scroll)
+            .or_else(|| (!self.outline.is_empty()).then_some(0))
+    }
+
+    pub fn outline_cursor(&self) -> Option<usize> {
+        match self.outline_selected {
+            Some(idx) if idx < self.outline.len() => Some(idx),
+            _ => self.current_section(),
+        }
+    }
+
+    pub fn outline_move_up(&mut self) {
+        if let Some(idx) = self.outline_cursor() {
+            self.outline_selected = Some(idx.saturating_sub(1));
+        }
+    }
+
+    pub fn outline_move_down(&mut self) {
+        if let Some(idx) = self.outline_cursor() {
+            self.outline_selected = Some((idx + 1).min(self.outline.len() - 1));
+        }
+    }
+
+    /// Scroll the preview so the heading under the outline cursor is at the top.
+    pub fn jump_to_outline(&mut self) {
+        let Some(row) = self.outline_cursor().map(|idx| self.outline[idx].row) else {
+            return;
+        };
+        self.record_jump();
+        self.preview_scroll = row;
+        self.outline_selected = None;
+        self.focus = Focus::Preview;
+    }
+
+    fn current_location(&self) -> Option<NavEntry> {
+        let path = self.selected_file()?.clone();
+        path.is_file().then_some(NavEntry {
+            path,
+            scroll: self.preview_scroll,
+        })
+    }
+
+    /// Remember the current file and scroll position before a jump.
+    pub fn record_jump(&mut self) {
+        if let Some(location) = self.current_location() {
+            self.nav_history.record(location);
+        }
+    }
+
+    /// Select `path` in the tree, remembering the current location as a
+    /// jump only if it could be revealed.
+    fn jump_to_path(&mut self, path: &Path) -> bool {
+        let origin = self.current_location();
+        let revealed = self.reveal_path(path);
+        if let Some(origin) = origin.filter(|_| revealed) {
+            self.nav_history.record(origin);
+        }
+        revealed
+    }
+
+    pub fn nav_back(&mut self) {
+        let Some(current) = self.current_location() else {
+            return;
+        };
+        match self.nav_history.back(current) {
+            Some(entry) => self.restore_location(entry),
+            None => self.status_message = Some("Already at oldest location".to_string()),
+        }
+    }
+
+    pub fn nav_forward(&mut self) {
+        let Some(current) = self.current_location() else {
+            return;
+        };
+        match self.nav_history.forward(current) {
+            Some(entry) => self.restore_location(entry),
+            None => self.status_message = Some("Already at newest location".to_string()),
+        }
+    }
+
+    fn restore_location(&mut self, entry: NavEntry) {
+        if self.reveal_path(&entry.path) {
+            self.preview_scroll = entry.scroll;
+            self.focus = Focus::Preview;
+        } else {
+            self.status_message = Some(format!("{} is no longer available", entry.path.display()));
+        }
+    }
+
+    /// Select `path` in the tree, expanding collapsed parents and dropping
+    /// filters that hide it. Returns false if the file is not in the tree.
+    pub fn reveal_path(&mut self, path: &Path) -> bool {
+        for ancestor in path.ancestors().skip(1) {
+            self.file_tree.collapsed.remove(ancestor);
+        }
+        let is_claude_md = path.file_name().map(|n| n == "CLAUDE.md").unwrap_or(false);
+        if self.file_tree.claude_only && !is_claude_md {
+            self.file_tree.toggle_claude_only();
+        }
+        self.file_tree.rebuild_flat_cache();
+        self.update_filter();
+
+        let flat = self.file_tree.flat_list();
+        let Some(actual_idx) = flat.iter().position(|(p, _)| p == path) else {
+            return false;
+        };
+        if !self.filtered_indices.contains(&actual_idx) {
+            self.search_query.clear();
+            self.update_filter();
+        }
+        let Some(display_idx) = self.filtered_indices.iter().position(|&i| i == actual_idx) else {
+            return false;
+        };
+
+        self.selected_index = display_idx;
+        self.preview_scroll = 0;
+        // Keep the selection inside the visible part of the tree
+        if self.selected_index < self.tree_scroll
+            || self.selected_index >= self.tree_scroll + self.tree_height
+        {
+            self.tree_scroll = self.selected_index.saturating_sub(self.tree_height / 2);
+        }
+        true
+    }
+
+    fn preview_options(&self) -> PreviewOptions {
+        PreviewOptions {
+            raw: self.raw_view,
+            images: self.show_images.then_some(self.image_settings),
+        }
+    }
+
+    /// Show images in the preview, or only their alt text.
+    pub fn toggle_images(&mut self) {
+        self.show_images = !self.show_images;
+        self.status_message = Some(if self.show_images {
+            format!("Images on ({})", self.image_settings.protocol.label())
+        } else {
+            "Images off".to_string()
+        });
+    }
+
+    /// Switch between rendered markdown and raw source, keeping the same
+    /// source line at the top.
+    pub fn toggle_raw_view(&mut self) {
+        self.raw_view = !self.raw_view;
+        self.preview_target_line = Some(self.preview_source_line);
+        self.clear_preview_search();
+        self.cancel_link_hint();
+    }
+
+    /// Switch the slides between rendered markdown and raw source. The
+    /// preview follows, as the setting is shared.
+    pub fn toggle_slides_raw_view(&mut self) {
+        self.toggle_raw_view();
+        self.mode = Mode::Slides;
+    }
+
+    /// Index into `preview_tasks` of the task cursor, if it is on a task of
+    /// the previewed file.
+    pub fn task_cursor(&self) -> Option<usize> {
+        let (path, idx) = self.task_cursor.as_ref()?;
+        (Some(path) == self.selected_file() && *idx < self.preview_tasks.len()).then_some(*idx)
+    }
+
+    /// Move the task cursor to the next task, starting from the first one
+    /// in view.
+    pub fn next_task(&mut self) {
+        let count = self.preview_tasks.len();
+        let idx = match self.task_cursor() {
+            Some(idx) => (idx + 1) % count,
+            None => self
+                .preview_tasks
+                .iter()
+                .position(|task| task.row >= self.preview_scroll)
+                .unwrap_or(0),
+        };
+        self.select_task(idx);
+    }
+
+    /// Move the task cursor to the previous task, starting from the last
+    /// one in view.
+    pub fn prev_task(&mut self) {
+        let count = self.preview_tasks.len();
+        let bottom = self.preview_scroll as usize + self.preview_height;
+        let idx = match self.task_cursor() {
+            Some(idx) => (idx + count - 1) % count,
+            None => self
+                .preview_tasks
+                .iter()
+                .rposition(|task| (task.row as usize) < bottom)
+                .unwrap_or(count.saturating_sub(1)),
+        };
+        self.select_task(idx);
+    }
+
+    fn select_task(&mut self, idx: usize) {
+        let (Some(path), Some(task)) = (self.selected_file().cloned(), self.preview_tasks.get(idx))
+        else {
+            self.status_message = Some("No tasks in this document".to_string());
+            return;
+        };
+        // Scroll only when the task is out of view
+        let row = task.row as usize;
+        let scroll = self.preview_scroll as usize;
+        if row < scroll || row >= scroll + self.preview_height {
+            self.preview_scroll = task.row.saturating_sub(self.preview_height as u16 / 2);
+        }
+        self.task_cursor = Some((path, idx));
+    }
+
+    /// Check or uncheck the task under the cursor in the source file. The
+    /// write is refused if the file changed since the preview was rendered.
+    pub fn toggle_task(&mut self) {
+        let (Some(idx), Some(path)) = (self.task_cursor(), self.selected_file()) else {
+            self.status_message = Some("No task selected; use [ and ] to pick one".to_string());
+            return;
+        };
+        let task = &self.preview_tasks[idx];
+        let rendered = self.preview_cache.mtime(path, self.preview_options());
+        self.status_message = Some(
+            match actions::toggle_task(path, task.offset, task.checked, rendered) {
+                Ok(true) => "Task checked".to_string(),
+                Ok(false) => "Task unchecked".to_string(),
+                Err(e) => e,
+            },
+        );
+    }
+
+    /// Whether the previewed file changed on disk since it was rendered.
+    pub fn preview_is_stale(&self) -> bool {
+        self.selected_file()
+            .is_some_and(|path| self.preview_cache.is_stale(path, self.preview_options()))
+    }
+
     pub fn toggle_help(&mut self) {
         self.mode = match self.mode {
             Mode::Help => Mode::Normal,
             _ => Mode::Help,
         };
+        self.help_scroll = 0;
+    }
+
+    pub fn scroll_help_up(&mut self) {
+        self.help_scroll = self.help_scroll.saturating_sub(1);
+    }
+
+    pub fn scroll_help_down(&mut self) {
+        self.help_scroll = self.help_scroll.saturating_add(1);
     }
 
     pub fn refresh(&mut self) {
         use crate::fs::scanner::scan_directories;
 
         self.file_tree = scan_directories();
+        self.fields_loaded = false;
+        self.heading_index = None;
+        self.heading_index_loading = None;
+        self.wiki_index = None;
+        // Wikilink styling depends on which files exist
+        self.preview_cache.clear();
+        if self.link_graph.is_some() || self.link_graph_loading.is_some() {
+            // An update under way may still use the old file list
+            self.link_graph_loading = None;
+            self.update_link_graph();
+        }
+        if self.search_index.is_some() || self.search_index_loading.is_some() {
+            self.search_index_loading = Some(update_in_background(
+                self.search_index.take(),
+                self.file_tree.markdown_files(),
+            ));
+        }
         self.update_filter();
         self.status_message = Some("Refreshed file list".to_string());
     }
@@ -0,0 +1,86 @@
+use std::fs;
+use std::path::Path;
+
+use crate::fs::index::{tokenize, SearchIndex};
+use crate::fs::scanner::scan_directories;
+use crate::ui::display_path;
+
+const DEFAULT_LIMIT: usize = 20;
+
+const USAGE: &str = "\
+Usage:
+  md-explorer                         Launch the file browser
+  md-explorer search [-n N] <query>   Full-text search of the scanned markdown
+  md-explorer help                    Show this message";
+
+/// Run a command-line subcommand and return the process exit code.
+pub fn run(args: &[String]) -> i32 {
+    match args[0].as_str() {
+        "search" => search(&args[1..]),
+        "help" | "-h" | "--help" => {
+            println!("{}", USAGE);
+            0
+        }
+        other => {
+            eprintln!("Unknown command '{}'\n\n{}", other, USAGE);
+            2
+        }
+    }
+}
+
+fn search(args: &[String]) -> i32 {
+    let mut limit = DEFAULT_LIMIT;
+    let mut words: Vec<&str> = Vec::new();
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-n" | "--limit" => match iter.next().and_then(|n| n.parse().ok()) {
+                Some(n) => limit = n,
+                None => {
+                    eprintln!("{} expects a number", arg);
+                    return 2;
+                }
+            },
+            word => words.push(word),
+        }
+    }
+
+    let query = words.join(" ");
+    if tokenize(&query).is_empty() {
+        eprintln!("Missing search query\n\n{}", USAGE);
+        return 2;
+    }
+
+    let file_tree = scan_directories();
+    let mut index = SearchIndex::load();
+    index.update(&file_tree.markdown_files());
+    index.save();
+
+    let hits = index.search(&query, limit);
+    if hits.is_empty() {
+        eprintln!("No matches for '{}'", query);
+        return 1;
+    }
+
+    let terms = tokenize(&query);
+    for hit in hits {
+        println!("{:>7.2}  {}", hit.score, display_path(&hit.path));
+        if let Some((line_no, line)) = first_matching_line(&hit.path, &terms) {
+            println!("         {}: {}", line_no, line);
+        }
+    }
+    0
+}
+
+/// The first line mentioning any of `terms`, for context under each result.
+fn first_matching_line(path: &Path, terms: &[String]) -> Option<(usize, String)> {
+    let content = fs::read_to_string(path).ok()?;
+    content.lines().enumerate().find_map(|(i, line)| {
+        let line_terms = tokenize(line);
+        terms
+            .iter()
+            .any(|term| line_terms.iter().any(|t| t.starts_with(term.as_str())))
+            .then(|| (i + 1, line.trim().chars().take(100).collect()))
+    })
+}
@@ -1,13 +1,118 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use fuzzy_matcher::skim::SkimMatcherV2;
 use fuzzy_matcher::FuzzyMatcher;
+use regex::RegexBuilder;
 
-pub fn fuzzy_filter(items: &[(PathBuf, usize)], query: &str) -> Vec<usize> {
+/// How the search query is matched against filenames.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    Fuzzy,
+    Exact,
+    Regex,
+    /// Full-text search over file contents, backed by the search index
+    Content,
+}
+
+impl MatchMode {
+    pub fn next(self) -> Self {
+        match self {
+            MatchMode::Fuzzy => MatchMode::Exact,
+            MatchMode::Exact => MatchMode::Regex,
+            MatchMode::Regex => MatchMode::Content,
+            MatchMode::Content => MatchMode::Fuzzy,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MatchMode::Fuzzy => "fuzzy",
+            MatchMode::Exact => "exact",
+            MatchMode::Regex => "regex",
+            MatchMode::Content => "content",
+        }
+    }
+}
+
+/// Filter the flat tree list by `query`, returning indices of matching files
+/// together with their parent directories, in tree order.
+///
+/// Content mode needs the search index and is handled by [`filter_by_paths`];
+/// here it matches nothing. Returns an error message if the query is not a
+/// valid regex in regex mode.
+pub fn filter_items(
+    items: &[(PathBuf, usize)],
+    query: &str,
+    mode: MatchMode,
+) -> Result<Vec<usize>, String> {
     if query.is_empty() {
-        return (0..items.len()).collect();
+        return Ok((0..items.len()).collect());
     }
 
+    let matched = match mode {
+        MatchMode::Fuzzy => fuzzy_matches(items, query),
+        MatchMode::Exact => exact_matches(items, query),
+        MatchMode::Regex => regex_matches(items, query)?,
+        MatchMode::Content => Vec::new(),
+    };
+
+    Ok(with_parents(items, matched))
+}
+
+/// Split `key:value` frontmatter terms, such as `status:draft`, out of a
+/// query. Only keys `is_field` accepts are taken, so words like `std::fs` or
+/// `http://host` stay in the query. Returns the rest of the query and the
+/// terms.
+pub fn split_field_terms(
+    query: &str,
+    is_field: impl Fn(&str) -> bool,
+) -> (String, Vec<(String, String)>) {
+    let mut rest = Vec::new();
+    let mut fields = Vec::new();
+    for word in query.split_whitespace() {
+        match word.split_once(':') {
+            Some((key, value))
+                if !value.is_empty()
+                    && key.starts_with(|c: char| c.is_alphabetic())
+                    && key
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+                    && is_field(key) =>
+            {
+                fields.push((key.to_string(), value.to_string()));
+            }
+            _ => rest.push(word),
+        }
+    }
+    (rest.join(" "), fields)
+}
+
+/// Keep only the given files (and their parent directories), in tree order.
+pub fn filter_by_paths(items: &[(PathBuf, usize)], paths: &[PathBuf]) -> Vec<usize> {
+    let wanted: std::collections::HashSet<&PathBuf> = paths.iter().collect();
+    let matched = items
+        .iter()
+        .enumerate()
+        .filter(|(_, (path, _))| wanted.contains(path))
+        .map(|(idx, _)| idx)
+        .collect();
+    with_parents(items, matched)
+}
+
+fn file_name(path: &Path) -> Option<String> {
+    // Only match files, not directories
+    if path.is_dir() {
+        return None;
+    }
+    path.file_name().map(|n| n.to_string_lossy().to_string())
+}
+
+/// Smart case: case-insensitive unless the query contains an uppercase letter.
+fn is_case_sensitive(query: &str) -> bool {
+    query.chars().any(|c| c.is_uppercase())
+}
+
+fn fuzzy_matches(items: &[(PathBuf, usize)], query: &str) -> Vec<usize> {
     let matcher = SkimMatcherV2::default();
     let query_lower = query.to_lowercase();
 
@@ -15,17 +120,7 @@ pub fn fuzzy_filter(items: &[(PathBuf, usize)], query: &str) -> Vec<usize> {
         .iter()
         .enumerate()
         .filter_map(|(idx, (path, _))| {
-            // Only match files, not directories
-            if path.is_dir() {
-                // Include directories that have matching children
-                return None;
-            }
-
-            let filename = path
-                .file_name()
-                .map(|n| n.to_string_lossy().to_lowercase())
-                .unwrap_or_default();
-
+            let filename = file_name(path)?.to_lowercase();
             matcher
                 .fuzzy_match(&filename, &query_lower)
                 .map(|score| (idx, score))
@@ -33,11 +128,51 @@ pub fn fuzzy_filter(items: &[(PathBuf, usize)], query: &str) -> Vec<usize> {
         .collect();
 
     // Sort by score descending
-    matches.sort_by(|a, b| b.1.cmp(&a.1));
+    matches.sort_by_key(|&(_, score)| std::cmp::Reverse(score));
+    matches.into_iter().map(|(idx, _)| idx).collect()
+}
+
+fn exact_matches(items: &[(PathBuf, usize)], query: &str) -> Vec<usize> {
+    let case_sensitive = is_case_sensitive(query);
+    let needle = if case_sensitive {
+        query.to_string()
+    } else {
+        query.to_lowercase()
+    };
+
+    items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, (path, _))| {
+            let filename = file_name(path)?;
+            let haystack = if case_sensitive {
+                filename
+            } else {
+                filename.to_lowercase()
+            };
+            haystack.contains(&needle).then_some(idx)
+        })
+        .collect()
+}
 
-    // Now include parent directories of matched files
-    let matched_file_indices: Vec<usize> = matches.iter().map(|(idx, _)| *idx).collect();
+fn regex_matches(items: &[(PathBuf, usize)], query: &str) -> Result<Vec<usize>, String> {
+    let re = RegexBuilder::new(query)
+        .case_insensitive(!is_case_sensitive(query))
+        .build()
+        .map_err(|e| match e {
+            regex::Error::Syntax(msg) => msg.lines().last().unwrap_or_default().to_string(),
+            other => other.to_string(),
+        })?;
+
+    Ok(items
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, (path, _))| re.is_match(&file_name(path)?).then_some(idx))
+        .collect())
+}
 
+/// Include the parent directories of every matched file so the tree keeps its shape.
+fn with_parents(items: &[(PathBuf, usize)], matched_file_indices: Vec<usize>) -> Vec<usize> {
     let mut result: Vec<usize> = Vec::new();
     let mut seen_parents: std::collections::HashSet<PathBuf> = std::collections::HashSet::new();
 
@@ -66,3 +201,86 @@ pub fn fuzzy_filter(items: &[(PathBuf, usize)], query: &str) -> Vec<usize> {
     result.sort();
     result
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory (which must exist to be told apart from files) and three
+    /// files in it.
+    fn items() -> Vec<(PathBuf, usize)> {
+        let dir = std::env::temp_dir();
+        let mut items = vec![(dir.clone(), 0)];
+        for name in ["Notes.md", "README.md", "notes-2024.md"] {
+            items.push((dir.join(name), 1));
+        }
+        items
+    }
+
+    fn filter(query: &str, mode: MatchMode) -> Result<Vec<usize>, String> {
+        filter_items(&items(), query, mode)
+    }
+
+    #[test]
+    fn empty_query_keeps_everything() {
+        assert_eq!(filter("", MatchMode::Exact), Ok(vec![0, 1, 2, 3]));
+    }
+
+    #[test]
+    fn exact_mode_matches_substrings_with_smart_case() {
+        assert_eq!(filter("notes", MatchMode::Exact), Ok(vec![0, 1, 3]));
+        assert_eq!(filter("Notes", MatchMode::Exact), Ok(vec![0, 1]));
+        assert_eq!(filter("nts", MatchMode::Exact), Ok(vec![]));
+        // Directories are only kept as parents of matches
+        let dir = std::env::temp_dir();
+        let dir_name = dir.file_name().unwrap().to_string_lossy();
+        assert_eq!(filter(&dir_name, MatchMode::Exact), Ok(vec![]));
+    }
+
+    #[test]
+    fn regex_mode_matches_with_smart_case() {
+        assert_eq!(filter(r"^notes-\d+\.md$", MatchMode::Regex), Ok(vec![0, 3]));
+        assert_eq!(filter("^n", MatchMode::Regex), Ok(vec![0, 1, 3]));
+        assert_eq!(filter("^N", MatchMode::Regex), Ok(vec![0, 1]));
+        assert_eq!(filter("^R.*E", MatchMode::Regex), Ok(vec![0, 2]));
+    }
+
+    #[test]
+    fn invalid_regex_reports_the_syntax_error() {
+        assert_eq!(
+            filter("notes(", MatchMode::Regex),
+            Err("error: unclosed group".to_string())
+        );
+        assert_eq!(
+            filter("[z-a]", MatchMode::Regex),
+            Err("error: invalid character class range, the start must be <= the end".to_string())
+        );
+    }
+
+    #[test]
+    fn content_mode_leaves_filtering_to_the_index() {
+        assert_eq!(filter("notes", MatchMode::Content), Ok(vec![]));
+        let paths = [std::env::temp_dir().join("README.md")];
+        assert_eq!(filter_by_paths(&items(), &paths), [0, 2]);
+    }
+
+    fn known(key: &str) -> bool {
+        matches!(key, "status" | "owner")
+    }
+
+    #[test]
+    fn known_keys_become_field_terms() {
+        let (rest, fields) = split_field_terms("status:draft adr", known);
+        assert_eq!(rest, "adr");
+        assert_eq!(fields, [("status".to_string(), "draft".to_string())]);
+    }
+
+    #[test]
+    fn other_colons_stay_in_the_query() {
+        for query in ["http://host", "TODO:", "std::fs", "note:foo"] {
+            let (rest, fields) = split_field_terms(query, known);
+            assert_eq!(rest, query);
+            assert!(fields.is_empty(), "{} was taken as a field", query);
+        }
+    }
+}
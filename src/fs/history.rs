@@ -0,0 +1,193 @@
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+
+const MAX_ENTRIES: usize = 500;
+
+/// Past search queries, oldest first, persisted next to the tree state.
+#[derive(Debug, Clone, Default)]
+pub struct SearchHistory {
+    entries: Vec<String>,
+    /// Position while cycling with Up/Down; `None` when editing a fresh query
+    cursor: Option<usize>,
+    /// The query that was being typed before cycling started
+    draft: String,
+}
+
+impl SearchHistory {
+    fn history_file_path() -> Option<PathBuf> {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|p| p.join("md-explorer").join("search_history"))
+    }
+
+    pub fn load() -> Self {
+        let mut history = Self::default();
+
+        let Some(path) = Self::history_file_path() else {
+            return history;
+        };
+
+        let Ok(file) = fs::File::open(&path) else {
+            return history;
+        };
+
+        let reader = BufReader::new(file);
+        history.entries = reader
+            .lines()
+            .map_while(Result::ok)
+            .filter(|line| !line.is_empty())
+            .collect();
+        history
+    }
+
+    pub fn save(&self) {
+        let Some(path) = Self::history_file_path() else {
+            return;
+        };
+
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+
+        let Ok(mut file) = fs::File::create(&path) else {
+            return;
+        };
+
+        for entry in &self.entries {
+            let _ = writeln!(file, "{}", entry);
+        }
+    }
+
+    /// Record a query as the most recent entry, dropping older duplicates.
+    pub fn push(&mut self, query: &str) {
+        self.reset_cursor();
+        let query = query.trim();
+        if query.is_empty() || query.contains('\n') {
+            return;
+        }
+
+        self.entries.retain(|e| e != query);
+        self.entries.push(query.to_string());
+        if self.entries.len() > MAX_ENTRIES {
+            let excess = self.entries.len() - MAX_ENTRIES;
+            self.entries.drain(..excess);
+        }
+    }
+
+    pub fn reset_cursor(&mut self) {
+        self.cursor = None;
+        self.draft.clear();
+    }
+
+    /// Step to the next older entry. `current` is remembered so that stepping
+    /// forward past the newest entry restores it.
+    pub fn previous(&mut self, current: &str) -> Option<&str> {
+        let idx = match self.cursor {
+            None => {
+                self.draft = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(idx) => idx.saturating_sub(1),
+        };
+        self.cursor = Some(idx);
+        self.entries.get(idx).map(String::as_str)
+    }
+
+    /// Step to the next newer entry, or back to the draft past the newest.
+    pub fn next(&mut self) -> Option<String> {
+        let idx = self.cursor?;
+        if idx + 1 < self.entries.len() {
+            self.cursor = Some(idx + 1);
+            self.entries.get(idx + 1).cloned()
+        } else {
+            let draft = std::mem::take(&mut self.draft);
+            self.cursor = None;
+            Some(draft)
+        }
+    }
+
+    /// Find the newest entry containing `needle` strictly older than `before`
+    /// (or anywhere, if `before` is `None`).
+    pub fn search_backward(&self, needle: &str, before: Option<usize>) -> Option<usize> {
+        let end = before.unwrap_or(self.entries.len()).min(self.entries.len());
+        let needle = needle.to_lowercase();
+        self.entries[..end]
+            .iter()
+            .rposition(|e| e.to_lowercase().contains(&needle))
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.entries.get(idx).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(entries: &[&str]) -> SearchHistory {
+        let mut history = SearchHistory::default();
+        for entry in entries {
+            history.push(entry);
+        }
+        history
+    }
+
+    #[test]
+    fn steps_through_entries_and_back_to_the_draft() {
+        let mut history = history(&["one", "two", "three"]);
+        assert_eq!(history.next(), None);
+        assert_eq!(history.previous("dra"), Some("three"));
+        assert_eq!(history.previous("three"), Some("two"));
+        assert_eq!(history.previous("two"), Some("one"));
+        // The oldest entry stays put
+        assert_eq!(history.previous("one"), Some("one"));
+        assert_eq!(history.next().as_deref(), Some("two"));
+        assert_eq!(history.next().as_deref(), Some("three"));
+        assert_eq!(history.next().as_deref(), Some("dra"));
+        assert_eq!(history.next(), None);
+    }
+
+    #[test]
+    fn empty_history_keeps_the_query() {
+        let mut history = SearchHistory::default();
+        assert_eq!(history.previous("typed"), None);
+        assert_eq!(history.next(), None);
+    }
+
+    #[test]
+    fn pushing_dedups_and_resets_the_cursor() {
+        let mut history = history(&["one", "two"]);
+        history.previous("");
+        history.push("  one ");
+        history.push("");
+        history.push("multi\nline");
+        assert_eq!(history.entries, ["two", "one"]);
+        assert_eq!(history.previous("x"), Some("one"));
+    }
+
+    #[test]
+    fn keeps_the_newest_entries() {
+        let mut history = SearchHistory::default();
+        for i in 0..MAX_ENTRIES + 10 {
+            history.push(&format!("query {}", i));
+        }
+        assert_eq!(history.entries.len(), MAX_ENTRIES);
+        assert_eq!(history.get(0), Some("query 10"));
+        assert_eq!(
+            history.get(MAX_ENTRIES - 1),
+            Some(format!("query {}", MAX_ENTRIES + 9).as_str())
+        );
+    }
+
+    #[test]
+    fn searches_backward_ignoring_case() {
+        let history = history(&["Rust notes", "python", "rusty", "go"]);
+        assert_eq!(history.search_backward("rust", None), Some(2));
+        assert_eq!(history.search_backward("rust", Some(2)), Some(0));
+        assert_eq!(history.search_backward("rust", Some(0)), None);
+        assert_eq!(history.search_backward("RUST", Some(99)), Some(2));
+        assert_eq!(history.search_backward("java", None), None);
+    }
+}
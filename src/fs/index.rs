@@ -0,0 +1,530 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::UNIX_EPOCH;
+
+use crate::markdown::frontmatter::{body_lines, frontmatter_len};
+
+const INDEX_HEADER: &str = "md-explorer-index 1";
+
+/// Relative weight of a term occurrence in each field when ranking.
+const HEADING_WEIGHT: f64 = 3.0;
+const FRONTMATTER_WEIGHT: f64 = 2.0;
+const BODY_WEIGHT: f64 = 1.0;
+
+// BM25 parameters
+const K1: f64 = 1.2;
+const B: f64 = 0.75;
+
+/// Per-field occurrence counts of a term within one document.
+#[derive(Debug, Clone, Copy, Default)]
+struct TermCounts {
+    body: u32,
+    heading: u32,
+    frontmatter: u32,
+}
+
+impl TermCounts {
+    fn weighted(&self) -> f64 {
+        self.body as f64 * BODY_WEIGHT
+            + self.heading as f64 * HEADING_WEIGHT
+            + self.frontmatter as f64 * FRONTMATTER_WEIGHT
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IndexedDoc {
+    path: PathBuf,
+    mtime: u128,
+    length: u32,
+    terms: HashMap<String, TermCounts>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub path: PathBuf,
+    pub score: f64,
+}
+
+/// Full-text index of the scanned markdown, persisted in the cache dir.
+///
+/// Documents are re-tokenized only when their mtime changes, so keeping the
+/// index current costs one `stat` per file.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    docs: Vec<IndexedDoc>,
+    /// term -> indices into `docs`; sorted so prefix lookups are a range scan
+    postings: BTreeMap<String, Vec<usize>>,
+    dirty: bool,
+}
+
+impl SearchIndex {
+    fn index_file_path() -> Option<PathBuf> {
+        dirs::cache_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|p| p.join("md-explorer").join("index"))
+    }
+
+    /// Load the index from disk, or start empty if it is missing or stale.
+    pub fn load() -> Self {
+        let Some(path) = Self::index_file_path() else {
+            return Self::default();
+        };
+
+        match fs::File::open(&path) {
+            Ok(file) => Self::read(BufReader::new(file)),
+            Err(_) => Self::default(),
+        }
+    }
+
+    /// Parse an index in the persisted line format, or start empty if the
+    /// header does not match.
+    fn read(reader: impl BufRead) -> Self {
+        let mut index = Self::default();
+        let mut lines = reader.lines().map_while(Result::ok);
+        if lines.next().as_deref() != Some(INDEX_HEADER) {
+            return index;
+        }
+
+        for line in lines {
+            let mut fields = line.split('\t');
+            match fields.next() {
+                Some("doc") => {
+                    let (Some(mtime), Some(length), Some(path)) =
+                        (fields.next(), fields.next(), fields.next())
+                    else {
+                        continue;
+                    };
+                    index.docs.push(IndexedDoc {
+                        path: PathBuf::from(path),
+                        mtime: mtime.parse().unwrap_or(0),
+                        length: length.parse().unwrap_or(0),
+                        terms: HashMap::new(),
+                    });
+                }
+                Some("t") => {
+                    let Some(doc) = index.docs.last_mut() else {
+                        continue;
+                    };
+                    let (Some(term), Some(body), Some(heading), Some(frontmatter)) =
+                        (fields.next(), fields.next(), fields.next(), fields.next())
+                    else {
+                        continue;
+                    };
+                    doc.terms.insert(
+                        term.to_string(),
+                        TermCounts {
+                            body: body.parse().unwrap_or(0),
+                            heading: heading.parse().unwrap_or(0),
+                            frontmatter: frontmatter.parse().unwrap_or(0),
+                        },
+                    );
+                }
+                _ => {}
+            }
+        }
+
+        index.rebuild_postings();
+        index
+    }
+
+    pub fn save(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        let Some(path) = Self::index_file_path() else {
+            return;
+        };
+
+        if let Some(parent) = path.parent() {
+            let _ = fs::create_dir_all(parent);
+        }
+
+        let Ok(file) = fs::File::create(&path) else {
+            return;
+        };
+
+        let mut out = BufWriter::new(file);
+        if self.write(&mut out).is_ok() && out.flush().is_ok() {
+            self.dirty = false;
+        }
+    }
+
+    /// Write the index in its line format: a header, then a `doc` line per
+    /// document followed by a `t` line per term.
+    fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "{}", INDEX_HEADER)?;
+        for doc in &self.docs {
+            writeln!(
+                out,
+                "doc\t{}\t{}\t{}",
+                doc.mtime,
+                doc.length,
+                doc.path.display()
+            )?;
+            for (term, counts) in &doc.terms {
+                writeln!(
+                    out,
+                    "t\t{}\t{}\t{}\t{}",
+                    term, counts.body, counts.heading, counts.frontmatter
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Bring the index in line with `files`: re-tokenize new or modified
+    /// files and drop ones that no longer exist. Returns how many were indexed.
+    pub fn update(&mut self, files: &[PathBuf]) -> usize {
+        let wanted: HashSet<&PathBuf> = files.iter().collect();
+        let before = self.docs.len();
+        self.docs.retain(|doc| wanted.contains(&doc.path));
+        let mut changed = self.docs.len() != before;
+
+        let positions: HashMap<PathBuf, usize> = self
+            .docs
+            .iter()
+            .enumerate()
+            .map(|(i, doc)| (doc.path.clone(), i))
+            .collect();
+
+        let mut indexed = 0;
+        for path in files {
+            // Paths that can't round-trip through the line format are skipped
+            let display = path.display().to_string();
+            if display.contains('\t') || display.contains('\n') {
+                continue;
+            }
+
+            let mtime = file_mtime(path);
+            let existing = positions.get(path).copied();
+            if existing.is_some_and(|i| self.docs[i].mtime == mtime) {
+                continue;
+            }
+
+            let Ok(content) = fs::read_to_string(path) else {
+                continue;
+            };
+            let (terms, length) = tokenize_document(&content);
+            let doc = IndexedDoc {
+                path: path.clone(),
+                mtime,
+                length,
+                terms,
+            };
+            match existing {
+                Some(i) => self.docs[i] = doc,
+                None => self.docs.push(doc),
+            }
+            indexed += 1;
+            changed = true;
+        }
+
+        if changed {
+            self.dirty = true;
+            self.rebuild_postings();
+        }
+        indexed
+    }
+
+    fn rebuild_postings(&mut self) {
+        self.postings.clear();
+        for (i, doc) in self.docs.iter().enumerate() {
+            for term in doc.terms.keys() {
+                self.postings.entry(term.clone()).or_default().push(i);
+            }
+        }
+    }
+
+    /// Rank documents containing every query term with BM25, weighting
+    /// heading and frontmatter hits above body text. The last term also
+    /// matches as a prefix so results update while a word is being typed.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let terms = tokenize(query);
+        if terms.is_empty() || self.docs.is_empty() {
+            return Vec::new();
+        }
+
+        let doc_count = self.docs.len() as f64;
+        let avg_length =
+            self.docs.iter().map(|d| d.length as f64).sum::<f64>() / doc_count.max(1.0);
+
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for (term_idx, term) in terms.iter().enumerate() {
+            let is_last = term_idx == terms.len() - 1;
+            let expansions: Vec<&String> = if is_last {
+                self.postings
+                    .range(term.clone()..)
+                    .take_while(|(t, _)| t.starts_with(term.as_str()))
+                    .map(|(t, _)| t)
+                    .collect()
+            } else {
+                self.postings
+                    .get_key_value(term)
+                    .map(|(t, _)| vec![t])
+                    .unwrap_or_default()
+            };
+
+            let mut term_scores: HashMap<usize, f64> = HashMap::new();
+            for expansion in expansions {
+                let postings = &self.postings[expansion];
+                let df = postings.len() as f64;
+                let idf = ((doc_count - df + 0.5) / (df + 0.5) + 1.0).ln();
+                for &doc_idx in postings {
+                    let doc = &self.docs[doc_idx];
+                    let tf = doc.terms[expansion].weighted();
+                    let norm = K1 * (1.0 - B + B * doc.length as f64 / avg_length.max(1.0));
+                    *term_scores.entry(doc_idx).or_default() += idf * tf * (K1 + 1.0) / (tf + norm);
+                }
+            }
+
+            // Every term has to match somewhere in the document
+            if term_idx == 0 {
+                scores = term_scores;
+            } else {
+                scores.retain(|doc_idx, _| term_scores.contains_key(doc_idx));
+                for (doc_idx, score) in scores.iter_mut() {
+                    *score += term_scores[doc_idx];
+                }
+            }
+        }
+
+        let mut hits: Vec<SearchHit> = scores
+            .into_iter()
+            .map(|(doc_idx, score)| SearchHit {
+                path: self.docs[doc_idx].path.clone(),
+                score,
+            })
+            .collect();
+        hits.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.path.cmp(&b.path)));
+        hits.truncate(limit);
+        hits
+    }
+}
+
+/// Load `index` from disk if it is `None`, then bring it up to date with
+/// `files` on a background thread, so the first content search does not
+/// block input.
+pub fn update_in_background(
+    index: Option<SearchIndex>,
+    files: Vec<PathBuf>,
+) -> Receiver<SearchIndex> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let mut index = index.unwrap_or_else(SearchIndex::load);
+        index.update(&files);
+        let _ = tx.send(index);
+    });
+    rx
+}
+
+fn file_mtime(path: &Path) -> u128 {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0)
+}
+
+/// Split text into lowercase alphanumeric terms.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Count terms per field. Returns the term table and the weighted document length.
+fn tokenize_document(content: &str) -> (HashMap<String, TermCounts>, u32) {
+    let mut terms: HashMap<String, TermCounts> = HashMap::new();
+    let mut length = 0u32;
+    let mut count = |text: &str, field: fn(&mut TermCounts) -> &mut u32| {
+        for term in tokenize(text) {
+            *field(terms.entry(term).or_default()) += 1;
+            length += 1;
+        }
+    };
+
+    let lines: Vec<&str> = content.lines().collect();
+    let body: Vec<(usize, &str, bool)> = body_lines(content).collect();
+
+    // Frontmatter: a leading block fenced by `---` (YAML) or `+++` (TOML)
+    let frontmatter = frontmatter_len(content);
+    for line in lines.iter().take(frontmatter.saturating_sub(1)).skip(1) {
+        count(line, |c| &mut c.frontmatter);
+    }
+
+    for (idx, &(_, line, in_code)) in body.iter().enumerate() {
+        let trimmed = line.trim_start();
+
+        if in_code {
+            count(line, |c| &mut c.body);
+            continue;
+        }
+
+        let hashes = trimmed.len() - trimmed.trim_start_matches('#').len();
+        let is_atx =
+            (1..=6).contains(&hashes) && trimmed[hashes..].chars().next().is_none_or(|c| c == ' ');
+        let is_setext = !trimmed.is_empty()
+            && body.get(idx + 1).is_some_and(|&(_, next, next_in_code)| {
+                let next = next.trim();
+                !next_in_code
+                    && !next.is_empty()
+                    && (next.chars().all(|c| c == '=') || next.chars().all(|c| c == '-'))
+            });
+        if is_atx || is_setext {
+            count(line, |c| &mut c.heading);
+        } else {
+            count(line, |c| &mut c.body);
+        }
+    }
+
+    (terms, length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    /// An index of in-memory documents, as if read from `path`s with mtime 1.
+    fn index(docs: &[(&str, &str)]) -> SearchIndex {
+        let mut index = SearchIndex::default();
+        for (path, content) in docs {
+            let (terms, length) = tokenize_document(content);
+            index.docs.push(IndexedDoc {
+                path: PathBuf::from(path),
+                mtime: 1,
+                length,
+                terms,
+            });
+        }
+        index.rebuild_postings();
+        index
+    }
+
+    fn paths(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.path.to_str().unwrap()).collect()
+    }
+
+    #[test]
+    fn tokenizes_into_lowercase_words() {
+        assert_eq!(
+            tokenize("Hello, wörld! snake_case x2"),
+            ["hello", "wörld", "snake", "case", "x2"]
+        );
+    }
+
+    #[test]
+    fn counts_terms_per_field() {
+        let content =
+            "---\ntags: rust\n---\n# Title\n\nSetext\n---\n\nbody rust\n\n```\nfenced\n---\n```\n";
+        let (terms, length) = tokenize_document(content);
+        let counts = |term: &str| {
+            let c = terms[term];
+            (c.body, c.heading, c.frontmatter)
+        };
+        assert_eq!(counts("tags"), (0, 0, 1));
+        assert_eq!(counts("rust"), (1, 0, 1));
+        assert_eq!(counts("title"), (0, 1, 0));
+        assert_eq!(counts("setext"), (0, 1, 0));
+        // A `---` inside code is not a setext underline
+        assert_eq!(counts("fenced"), (1, 0, 0));
+        assert_eq!(length, 7);
+    }
+
+    #[test]
+    fn ranks_headings_and_frequent_terms_first() {
+        let index = index(&[
+            ("body.md", "# Other\n\nsome text about parsers\n"),
+            ("heading.md", "# Parsers\n\nsome text\n"),
+            ("twice.md", "# Other\n\nparsers and more parsers\n"),
+            ("none.md", "# Unrelated\n"),
+        ]);
+        assert_eq!(
+            paths(&index.search("parsers", 10)),
+            ["heading.md", "twice.md", "body.md"]
+        );
+        assert_eq!(paths(&index.search("parsers", 1)), ["heading.md"]);
+    }
+
+    #[test]
+    fn requires_every_term_and_expands_the_last() {
+        let index = index(&[
+            ("a.md", "render the markdown\n"),
+            ("b.md", "rendering only\n"),
+            ("c.md", "markdown only\n"),
+        ]);
+        assert_eq!(paths(&index.search("markdown rend", 10)), ["a.md"]);
+        assert_eq!(paths(&index.search("rend", 10)).len(), 2);
+        // Only the last term is a prefix
+        assert!(index.search("rend markdown", 10).is_empty());
+        assert!(index.search("", 10).is_empty());
+    }
+
+    #[test]
+    fn round_trips_the_line_format() {
+        let index = index(&[("a.md", "# Title\n\nbody\n"), ("dir/b c.md", "more body\n")]);
+        let mut out = Vec::new();
+        index.write(&mut out).unwrap();
+        let read = SearchIndex::read(out.as_slice());
+        assert_eq!(read.docs.len(), 2);
+        for (before, after) in index.docs.iter().zip(&read.docs) {
+            assert_eq!(before.path, after.path);
+            assert_eq!(before.mtime, after.mtime);
+            assert_eq!(before.length, after.length);
+            assert_eq!(before.terms.len(), after.terms.len());
+            for (term, counts) in &before.terms {
+                let read = after.terms[term];
+                assert_eq!(
+                    (counts.body, counts.heading, counts.frontmatter),
+                    (read.body, read.heading, read.frontmatter)
+                );
+            }
+        }
+        assert_eq!(paths(&read.search("title", 10)), ["a.md"]);
+
+        let stale = SearchIndex::read("md-explorer-index 0\ndoc\t1\t1\ta.md\n".as_bytes());
+        assert!(stale.docs.is_empty());
+    }
+
+    #[test]
+    fn updates_only_changed_files() {
+        let dir = env::temp_dir().join(format!("md-explorer-index-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let a = dir.join("a.md");
+        let b = dir.join("b.md");
+        fs::write(&a, "alpha\n").unwrap();
+        fs::write(&b, "beta\n").unwrap();
+
+        let mut index = SearchIndex::default();
+        assert_eq!(index.update(&[a.clone(), b.clone()]), 2);
+        assert!(index.dirty);
+        assert_eq!(index.update(&[a.clone(), b.clone()]), 0);
+
+        fs::write(&a, "gamma\n").unwrap();
+        let later =
+            fs::metadata(&a).unwrap().modified().unwrap() + std::time::Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&a)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert_eq!(index.update(&[a.clone(), b.clone()]), 1);
+        assert!(index.search("alpha", 10).is_empty());
+        assert_eq!(index.search("gamma", 10).len(), 1);
+
+        // Files no longer wanted are dropped
+        assert_eq!(index.update(std::slice::from_ref(&a)), 0);
+        assert!(index.search("beta", 10).is_empty());
+        assert_eq!(index.docs.len(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,2 +1,4 @@
 pub mod filter;
+pub mod history;
+pub mod index;
 pub mod scanner;
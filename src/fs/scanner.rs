@@ -1,10 +1,55 @@
-use std::collections::{BTreeMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
 use ignore::WalkBuilder;
 
+use crate::markdown::frontmatter::{read_frontmatter, Frontmatter};
+
+/// Order of files within each directory of the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Name,
+    /// Frontmatter `title`, falling back to the filename
+    Title,
+    /// Frontmatter `date`, newest first; undated files last
+    Date,
+}
+
+impl SortOrder {
+    pub fn next(self) -> Self {
+        match self {
+            SortOrder::Name => SortOrder::Title,
+            SortOrder::Title => SortOrder::Date,
+            SortOrder::Date => SortOrder::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::Name => "name",
+            SortOrder::Title => "title",
+            SortOrder::Date => "date",
+        }
+    }
+
+    fn from_label(label: &str) -> Option<Self> {
+        [SortOrder::Name, SortOrder::Title, SortOrder::Date]
+            .into_iter()
+            .find(|order| order.label() == label)
+    }
+}
+
+/// A file's frontmatter and the modification time it was read at.
+#[derive(Debug, Clone)]
+struct CachedFrontmatter {
+    mtime: Option<SystemTime>,
+    frontmatter: Option<Frontmatter>,
+}
+
 #[derive(Debug, Clone)]
 pub struct FileTree {
     pub roots: Vec<PathBuf>,
@@ -15,6 +60,10 @@ pub struct FileTree {
     pub dirs_with_claude_md: HashSet<PathBuf>,
     pub show_empty_dirs: bool,
     pub claude_only: bool,
+    pub sort_order: SortOrder,
+    /// Parsed frontmatter of the markdown files, kept current by
+    /// [`FileTree::load_frontmatter`]
+    frontmatter: HashMap<PathBuf, CachedFrontmatter>,
 }
 
 impl FileTree {
@@ -28,6 +77,8 @@ impl FileTree {
             dirs_with_claude_md: HashSet::new(),
             show_empty_dirs: false,
             claude_only: false,
+            sort_order: SortOrder::Name,
+            frontmatter: HashMap::new(),
         }
     }
 
@@ -40,6 +91,9 @@ impl FileTree {
     }
 
     fn build_flat_cache(&mut self) {
+        if self.sort_order != SortOrder::Name {
+            self.load_frontmatter();
+        }
         self.flat_cache.clear();
         for root in &self.roots.clone() {
             self.flatten_dir(root, 0);
@@ -59,7 +113,7 @@ impl FileTree {
             let mut files: Vec<_> = children.iter().filter(|p| p.is_file()).collect();
 
             dirs.sort();
-            files.sort();
+            self.sort_files(&mut files);
 
             // Show files first, then subdirectories
             for file in files {
@@ -90,6 +144,88 @@ impl FileTree {
         }
     }
 
+    fn sort_files(&self, files: &mut [&PathBuf]) {
+        let field = |path: &Path, key: &str| {
+            self.frontmatter(path)
+                .and_then(|fm| fm.get(key))
+                .filter(|v| !v.is_empty())
+                .map(str::to_lowercase)
+        };
+        match self.sort_order {
+            SortOrder::Name => files.sort(),
+            SortOrder::Title => files.sort_by_cached_key(|path| {
+                let name = path
+                    .file_stem()
+                    .map(|n| n.to_string_lossy().to_lowercase())
+                    .unwrap_or_default();
+                (field(path, "title").unwrap_or(name), (*path).clone())
+            }),
+            // ISO dates sort correctly as strings
+            SortOrder::Date => files.sort_by_cached_key(|path| {
+                let date = field(path, "date");
+                (date.is_none(), Reverse(date), (*path).clone())
+            }),
+        }
+    }
+
+    /// Read the frontmatter of every markdown file that is new or has
+    /// changed since it was last read.
+    pub fn load_frontmatter(&mut self) {
+        let files = self.markdown_files();
+        let wanted: HashSet<&PathBuf> = files.iter().collect();
+        self.frontmatter.retain(|path, _| wanted.contains(path));
+        for path in &files {
+            let mtime = fs::metadata(path).and_then(|m| m.modified()).ok();
+            if self
+                .frontmatter
+                .get(path)
+                .is_some_and(|cached| cached.mtime.is_some() && cached.mtime == mtime)
+            {
+                continue;
+            }
+            self.frontmatter.insert(
+                path.clone(),
+                CachedFrontmatter {
+                    mtime,
+                    frontmatter: read_frontmatter(path),
+                },
+            );
+        }
+    }
+
+    /// Frontmatter of `path`, once [`FileTree::load_frontmatter`] has run.
+    pub fn frontmatter(&self, path: &Path) -> Option<&Frontmatter> {
+        self.frontmatter.get(path)?.frontmatter.as_ref()
+    }
+
+    /// Whether any file's frontmatter has field `key`, ignoring case.
+    pub fn has_frontmatter_key(&self, key: &str) -> bool {
+        self.frontmatter
+            .values()
+            .filter_map(|cached| cached.frontmatter.as_ref())
+            .any(|fm| fm.get(key).is_some())
+    }
+
+    pub fn cycle_sort_order(&mut self) -> SortOrder {
+        self.sort_order = self.sort_order.next();
+        self.build_flat_cache();
+        self.sort_order
+    }
+
+    /// Every markdown file found by the scan, regardless of filters or collapsed state.
+    pub fn markdown_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self
+            .entries
+            .values()
+            .flatten()
+            .filter(|p| p.is_file())
+            .cloned()
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
     pub fn toggle_collapsed(&mut self, path: &PathBuf) -> bool {
         if self.collapsed.contains(path) {
             self.collapsed.remove(path);
@@ -164,6 +300,8 @@ impl FileTree {
                 self.show_empty_dirs = true;
             } else if line == "claude_only:true" {
                 self.claude_only = true;
+            } else if let Some(order) = line.strip_prefix("sort:").and_then(SortOrder::from_label) {
+                self.sort_order = order;
             }
         }
     }
@@ -192,6 +330,10 @@ impl FileTree {
         if self.claude_only {
             let _ = writeln!(file, "claude_only:true");
         }
+
+        if self.sort_order != SortOrder::Name {
+            let _ = writeln!(file, "sort:{}", self.sort_order.label());
+        }
     }
 }
 
@@ -1,24 +1,37 @@
 mod actions;
 mod app;
+mod cli;
 mod fs;
+mod markdown;
+mod navigation;
+mod theme;
 mod ui;
 
-use std::io;
+use std::io::{self, Write};
 use std::time::Duration;
 
+use crossterm::cursor::MoveTo;
 use crossterm::event::{self, Event, KeyCode, KeyModifiers};
-use crossterm::execute;
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
-use ratatui::backend::CrosstermBackend;
+use crossterm::{execute, queue};
+use ratatui::backend::{Backend, CrosstermBackend};
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
 use ratatui::Terminal;
 
-use app::{App, Focus, Mode};
+use app::{App, Focus, ImagePlacement, Mode};
 use fs::scanner::scan_directories;
+use markdown::image::{ImageProtocol, KITTY_DELETE_ALL};
 use ui::layout::render;
 
 fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        std::process::exit(cli::run(&args));
+    }
+
     // Setup terminal
     enable_raw_mode()?;
     let mut stdout = io::stdout();
@@ -32,9 +45,13 @@ fn main() -> io::Result<()> {
 
     // Main loop
     let result = run_app(&mut terminal, &mut app);
+    forget_images(&mut terminal, &mut app)?;
 
     // Save state before exiting
     app.file_tree.save_state();
+    if let Some(index) = &mut app.search_index {
+        index.save();
+    }
 
     // Restore terminal
     disable_raw_mode()?;
@@ -49,29 +66,75 @@ fn main() -> io::Result<()> {
 }
 
 fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App) -> io::Result<()> {
+    // Only draw when input arrived or the previewed file changed on disk
+    let mut needs_redraw = true;
     loop {
-        terminal.draw(|f| render(f, app))?;
+        if app.receive_previews()
+            | app.receive_search_index()
+            | app.receive_heading_index()
+            | app.receive_link_graph()
+        {
+            needs_redraw = true;
+        }
+        if needs_redraw {
+            let screen = terminal.draw(|f| render(f, app))?.buffer;
+            if app.image_placements != app.images_on_screen {
+                let screen = screen.clone();
+                draw_images(terminal, app, &screen)?;
+            }
+            needs_redraw = false;
+        }
+
+        if !event::poll(Duration::from_millis(100))? {
+            needs_redraw = app.preview_is_stale() | app.reload_stale_slides();
+            app.recheck_link_graph();
+            continue;
+        }
+
+        match event::read()? {
+            Event::Key(key) => {
+                // Status messages last until the next key press
+                app.status_message = None;
 
-        if event::poll(Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
                 // Handle Ctrl+C globally
                 if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
                     app.should_quit = true;
                 }
 
                 match app.mode {
-                    Mode::Help => {
-                        // Any key closes help
-                        app.toggle_help();
-                    }
+                    Mode::Help => match key.code {
+                        KeyCode::Up | KeyCode::Char('k') => app.scroll_help_up(),
+                        KeyCode::Down | KeyCode::Char('j') => app.scroll_help_down(),
+                        // Any other key closes help
+                        _ => app.toggle_help(),
+                    },
                     Mode::Search => {
-                        handle_search_input(app, key.code);
+                        handle_search_input(app, key.code, key.modifiers);
+                    }
+                    Mode::PreviewSearch => {
+                        handle_preview_search_input(app, key.code);
+                    }
+                    Mode::HeadingSearch => {
+                        handle_heading_search_input(app, key.code, key.modifiers);
+                    }
+                    Mode::Todos => {
+                        handle_todos_input(app, key.code);
+                    }
+                    Mode::Slides => {
+                        handle_slides_input(app, key.code);
                     }
                     Mode::Normal => {
                         handle_normal_input(app, key.code, key.modifiers, terminal)?;
                     }
                 }
+                needs_redraw = true;
+            }
+            Event::Resize(..) => {
+                // The resize clears the screen, images included
+                forget_images(terminal, app)?;
+                needs_redraw = true;
             }
+            _ => {}
         }
 
         if app.should_quit {
@@ -80,6 +143,71 @@ fn run_app(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>, app: &mut App)
     }
 }
 
+/// Write graphics protocol images over the frame just drawn, first taking
+/// down the ones that moved or went out of view. Kitty deletes them, keeping
+/// the data of images still shown so they are only placed again; other
+/// protocols have the cells under them repainted from `screen`.
+fn draw_images(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+    screen: &Buffer,
+) -> io::Result<()> {
+    let kitty = app.image_settings.protocol == ImageProtocol::Kitty;
+    let backend = terminal.backend_mut();
+    let is_shown =
+        |placements: &[ImagePlacement], id: u32| placements.iter().any(|p| p.graphics.id == id);
+
+    for old in &app.images_on_screen {
+        if app.image_placements.contains(old) {
+            continue;
+        }
+        if kitty {
+            let keep_data = is_shown(&app.image_placements, old.graphics.id);
+            write!(backend, "{}", old.graphics.delete(keep_data))?;
+        } else {
+            let area = Rect::new(old.x, old.y, old.graphics.cols, old.graphics.rows)
+                .intersection(screen.area);
+            Backend::draw(backend, area.positions().map(|p| (p.x, p.y, &screen[p])))?;
+        }
+    }
+
+    for image in &app.image_placements {
+        if app.images_on_screen.contains(image) {
+            continue;
+        }
+        queue!(backend, MoveTo(image.x, image.y))?;
+        if !kitty {
+            write!(backend, "{}", image.graphics.sequence)?;
+        } else if is_shown(&app.images_on_screen, image.graphics.id) {
+            write!(backend, "{}", image.graphics.place())?;
+        } else {
+            write!(
+                backend,
+                "{}{}",
+                image.graphics.sequence,
+                image.graphics.place()
+            )?;
+        }
+    }
+    Write::flush(backend)?;
+    app.images_on_screen = app.image_placements.clone();
+    Ok(())
+}
+
+/// Forget the images on screen once it has been cleared, freeing the data
+/// kitty keeps for them.
+fn forget_images(
+    terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
+    app: &mut App,
+) -> io::Result<()> {
+    if app.image_settings.protocol == ImageProtocol::Kitty && !app.images_on_screen.is_empty() {
+        write!(terminal.backend_mut(), "{}", KITTY_DELETE_ALL)?;
+        Write::flush(terminal.backend_mut())?;
+    }
+    app.images_on_screen.clear();
+    Ok(())
+}
+
 fn handle_normal_input(
     app: &mut App,
     key: KeyCode,
@@ -87,20 +215,34 @@ fn handle_normal_input(
     terminal: &mut Terminal<CrosstermBackend<io::Stdout>>,
 ) -> io::Result<()> {
     let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+    let alt = modifiers.contains(KeyModifiers::ALT);
 
     match app.focus {
         Focus::Tree => match key {
             KeyCode::Char('q') => app.should_quit = true,
             KeyCode::Char('?') => app.toggle_help(),
             KeyCode::Char('/') => app.enter_search_mode(),
+            KeyCode::Char('#') => app.enter_heading_search(),
+            KeyCode::Char('t') => app.enter_todos(),
+            KeyCode::Char('p') => app.enter_slides(),
             KeyCode::Char('r') | KeyCode::Char('R') => app.refresh(),
             KeyCode::Char('.') => app.toggle_show_empty_dirs(),
             KeyCode::Char('c') => app.toggle_claude_only(),
+            KeyCode::Char('s') => app.cycle_sort_order(),
+            KeyCode::Char('b') => app.toggle_backlinks(),
+            KeyCode::Char('o') if ctrl => app.nav_back(),
+            KeyCode::Left if alt => app.nav_back(),
+            KeyCode::Right if alt => app.nav_forward(),
+            KeyCode::Char('i') if ctrl => app.nav_forward(),
+            KeyCode::Char('o') => app.toggle_outline(),
+            KeyCode::Char('v') => app.toggle_raw_view(),
+            KeyCode::Char('i') => app.toggle_images(),
             KeyCode::Up | KeyCode::Char('k') if ctrl => app.scroll_tree_up(),
             KeyCode::Down | KeyCode::Char('j') if ctrl => app.scroll_tree_down(),
             KeyCode::Up | KeyCode::Char('k') => app.move_up(),
             KeyCode::Down | KeyCode::Char('j') => app.move_down(),
             KeyCode::Char(' ') => app.toggle_focus(),
+            // Terminals report Ctrl-I as Tab, which folds here; Alt-→ jumps forward
             KeyCode::Tab => app.toggle_collapse(),
             KeyCode::Enter => {
                 if let Some(path) = app.selected_file() {
@@ -121,6 +263,7 @@ fn handle_normal_input(
                         execute!(terminal.backend_mut(), EnterAlternateScreen)?;
                         terminal.hide_cursor()?;
                         terminal.clear()?;
+                        forget_images(terminal, app)?;
                     }
                 }
             }
@@ -130,29 +273,160 @@ fn handle_normal_input(
         Focus::Preview => match key {
             KeyCode::Char('q') => app.should_quit = true,
             KeyCode::Char('?') => app.toggle_help(),
-            KeyCode::Char('/') => app.enter_search_mode(),
+            KeyCode::Char('/') => app.enter_preview_search(),
+            KeyCode::Char('#') => app.enter_heading_search(),
+            KeyCode::Char('t') => app.enter_todos(),
+            KeyCode::Char('p') => app.enter_slides(),
+            KeyCode::Char('o') if ctrl => app.nav_back(),
+            KeyCode::Left if alt => app.nav_back(),
+            KeyCode::Right if alt => app.nav_forward(),
+            // Terminals report Ctrl-I as Tab
+            KeyCode::Char('i') if ctrl => app.nav_forward(),
+            KeyCode::Tab => app.nav_forward(),
+            KeyCode::Char('o') => app.toggle_outline(),
+            KeyCode::Char('b') => app.toggle_backlinks(),
+            KeyCode::Char('v') => app.toggle_raw_view(),
+            KeyCode::Char('i') => app.toggle_images(),
+            KeyCode::Char('n') => app.next_preview_match(),
+            KeyCode::Char('N') => app.prev_preview_match(),
+            KeyCode::Char(']') => app.next_task(),
+            KeyCode::Char('[') => app.prev_task(),
+            KeyCode::Char('x') => app.toggle_task(),
             KeyCode::Char(' ') => app.toggle_focus(),
             KeyCode::Up | KeyCode::Char('k') => app.scroll_preview_up(),
             KeyCode::Down | KeyCode::Char('j') => app.scroll_preview_down(),
+            KeyCode::Char(c) if c.is_ascii_digit() => app.push_link_digit(c),
+            KeyCode::Enter if !app.link_hint_input.is_empty() => app.follow_link_hint(),
+            KeyCode::Esc if !app.link_hint_input.is_empty() => app.cancel_link_hint(),
+            KeyCode::Esc if !app.preview_query.is_empty() => app.clear_preview_search(),
             KeyCode::Esc => app.toggle_focus(),
             _ => {}
         },
+        Focus::Backlinks => match key {
+            KeyCode::Char('q') => app.should_quit = true,
+            KeyCode::Char('?') => app.toggle_help(),
+            KeyCode::Char('b') => app.toggle_backlinks(),
+            KeyCode::Char('o') if ctrl => app.nav_back(),
+            KeyCode::Left if alt => app.nav_back(),
+            KeyCode::Right if alt => app.nav_forward(),
+            KeyCode::Char('i') if ctrl => app.nav_forward(),
+            KeyCode::Char('o') => app.toggle_outline(),
+            KeyCode::Up | KeyCode::Char('k') => app.backlinks_move_up(),
+            KeyCode::Down | KeyCode::Char('j') => app.backlinks_move_down(),
+            KeyCode::Enter => app.open_backlink(),
+            KeyCode::Char(' ') => app.toggle_focus(),
+            KeyCode::Esc => app.focus = Focus::Preview,
+            _ => {}
+        },
+        Focus::Outline => match key {
+            KeyCode::Char('q') => app.should_quit = true,
+            KeyCode::Char('?') => app.toggle_help(),
+            KeyCode::Char('b') => app.toggle_backlinks(),
+            KeyCode::Char('o') if ctrl => app.nav_back(),
+            KeyCode::Left if alt => app.nav_back(),
+            KeyCode::Right if alt => app.nav_forward(),
+            KeyCode::Char('i') if ctrl => app.nav_forward(),
+            KeyCode::Char('o') => app.toggle_outline(),
+            KeyCode::Up | KeyCode::Char('k') => app.outline_move_up(),
+            KeyCode::Down | KeyCode::Char('j') => app.outline_move_down(),
+            KeyCode::Enter => app.jump_to_outline(),
+            KeyCode::Char(' ') => app.toggle_focus(),
+            KeyCode::Esc => app.focus = Focus::Preview,
+            _ => {}
+        },
         Focus::Search => {}
     }
     Ok(())
 }
 
-fn handle_search_input(app: &mut App, key: KeyCode) {
+fn handle_preview_search_input(app: &mut App, key: KeyCode) {
     match key {
-        KeyCode::Esc => app.clear_search(),
-        KeyCode::Enter => {
-            app.exit_search_mode();
-            if !app.filtered_indices.is_empty() {
-                app.selected_index = 0;
+        KeyCode::Esc => app.clear_preview_search(),
+        KeyCode::Enter => app.accept_preview_search(),
+        KeyCode::Backspace => app.pop_preview_search_char(),
+        KeyCode::Char(c) => app.push_preview_search_char(c),
+        _ => {}
+    }
+}
+
+fn handle_heading_search_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
+    let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+
+    match key {
+        KeyCode::Esc => app.exit_heading_search(),
+        KeyCode::Enter => app.pick_heading(),
+        KeyCode::Up => app.heading_move_up(),
+        KeyCode::Down => app.heading_move_down(),
+        KeyCode::Char('p') if ctrl => app.heading_move_up(),
+        KeyCode::Char('n') if ctrl => app.heading_move_down(),
+        KeyCode::Backspace => app.pop_heading_char(),
+        KeyCode::Char(c) if !ctrl => app.push_heading_char(c),
+        _ => {}
+    }
+}
+
+fn handle_todos_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc | KeyCode::Char('t') | KeyCode::Char('q') => app.exit_todos(),
+        KeyCode::Enter => app.pick_todo(),
+        KeyCode::Up | KeyCode::Char('k') => app.todo_move_up(),
+        KeyCode::Down | KeyCode::Char('j') => app.todo_move_down(),
+        KeyCode::Char('g') => app.cycle_todo_group(),
+        KeyCode::Char('f') => app.cycle_todo_filter(),
+        _ => {}
+    }
+}
+
+fn handle_slides_input(app: &mut App, key: KeyCode) {
+    match key {
+        KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('p') => app.exit_slides(),
+        KeyCode::Right
+        | KeyCode::Char('l')
+        | KeyCode::Char(' ')
+        | KeyCode::PageDown
+        | KeyCode::Enter => app.next_slide(),
+        KeyCode::Left | KeyCode::Char('h') | KeyCode::PageUp | KeyCode::Backspace => {
+            app.prev_slide()
+        }
+        KeyCode::Home | KeyCode::Char('g') => app.first_slide(),
+        KeyCode::End | KeyCode::Char('G') => app.last_slide(),
+        KeyCode::Down | KeyCode::Char('j') => app.scroll_slide_down(),
+        KeyCode::Up | KeyCode::Char('k') => app.scroll_slide_up(),
+        KeyCode::Char('s') => app.toggle_slide_notes(),
+        KeyCode::Char('i') => app.toggle_images(),
+        KeyCode::Char('v') => app.toggle_slides_raw_view(),
+        _ => {}
+    }
+}
+
+fn handle_search_input(app: &mut App, key: KeyCode, modifiers: KeyModifiers) {
+    let ctrl = modifiers.contains(KeyModifiers::CONTROL);
+
+    if app.reverse_search.is_some() {
+        match key {
+            KeyCode::Char('r') if ctrl => app.reverse_search_step(),
+            KeyCode::Esc => app.cancel_reverse_search(),
+            KeyCode::Backspace => app.pop_reverse_search_char(),
+            KeyCode::Char(c) if !ctrl => app.push_reverse_search_char(c),
+            // Like readline, any other key accepts the match and then acts as
+            // usual, so Enter applies it and the arrows move through history
+            _ => {
+                app.accept_reverse_search();
+                handle_search_input(app, key, modifiers);
             }
         }
+        return;
+    }
+
+    match key {
+        KeyCode::Char('r') if ctrl => app.reverse_search_step(),
+        KeyCode::Esc => app.clear_search(),
+        KeyCode::Enter => app.accept_search(),
         KeyCode::Backspace => app.pop_search_char(),
-        KeyCode::Char(c) => app.push_search_char(c),
+        KeyCode::Tab => app.cycle_match_mode(),
+        KeyCode::Up => app.history_previous(),
+        KeyCode::Down => app.history_next(),
+        KeyCode::Char(c) if !ctrl => app.push_search_char(c),
         _ => {}
     }
 }
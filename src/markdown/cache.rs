@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::markdown::loader::PreviewOptions;
+use crate::markdown::render::RenderedDocument;
+
+/// Rendered documents kept at once; the least recently used is dropped first.
+const MAX_ENTRIES: usize = 32;
+
+struct CacheEntry {
+    /// `None` for files that could not be stat'ed, whose notice stays valid
+    /// until the file appears
+    mtime: Option<SystemTime>,
+    width: u16,
+    document: Arc<RenderedDocument>,
+    last_used: u64,
+}
+
+/// Rendered previews keyed by path and preview options, so switching views
+/// keeps each one cached, and valid while the file's modification time and
+/// the pane width stay the same.
+#[derive(Default)]
+pub struct RenderCache {
+    entries: HashMap<(PathBuf, PreviewOptions), CacheEntry>,
+    clock: u64,
+}
+
+impl RenderCache {
+    /// The cached rendering of `path` at `width`, if the file is unchanged.
+    pub fn get(
+        &mut self,
+        path: &Path,
+        width: u16,
+        options: PreviewOptions,
+    ) -> Option<Arc<RenderedDocument>> {
+        let mtime = modified(path);
+        self.clock += 1;
+        let entry = self.entries.get_mut(&(path.to_path_buf(), options))?;
+        if entry.mtime != mtime || entry.width != width {
+            return None;
+        }
+        entry.last_used = self.clock;
+        Some(Arc::clone(&entry.document))
+    }
+
+    /// The last rendering of `path` at `width`, even if the file has changed
+    /// since, to keep on screen while it is rendered again.
+    pub fn get_stale(
+        &self,
+        path: &Path,
+        width: u16,
+        options: PreviewOptions,
+    ) -> Option<Arc<RenderedDocument>> {
+        self.entries
+            .get(&(path.to_path_buf(), options))
+            .filter(|entry| entry.width == width)
+            .map(|entry| Arc::clone(&entry.document))
+    }
+
+    /// Store a rendering of `path` made when it had modification time
+    /// `mtime`, or `None` if it could not be read.
+    pub fn insert(
+        &mut self,
+        path: &Path,
+        mtime: Option<SystemTime>,
+        width: u16,
+        options: PreviewOptions,
+        document: RenderedDocument,
+    ) {
+        let key = (path.to_path_buf(), options);
+        if self.entries.len() >= MAX_ENTRIES && !self.entries.contains_key(&key) {
+            if let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.entries.insert(
+            key,
+            CacheEntry {
+                mtime,
+                width,
+                document: Arc::new(document),
+                last_used: self.clock,
+            },
+        );
+    }
+
+    /// Whether `path` has changed on disk since it was cached with `options`.
+    pub fn is_stale(&self, path: &Path, options: PreviewOptions) -> bool {
+        self.entries
+            .get(&(path.to_path_buf(), options))
+            .is_some_and(|entry| modified(path) != entry.mtime)
+    }
+
+    /// Modification time of `path` when its rendering with `options` was made.
+    pub fn mtime(&self, path: &Path, options: PreviewOptions) -> Option<SystemTime> {
+        self.entries
+            .get(&(path.to_path_buf(), options))
+            .and_then(|entry| entry.mtime)
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const OPTIONS: PreviewOptions = PreviewOptions {
+        raw: false,
+        images: None,
+    };
+
+    fn document(width: u16) -> RenderedDocument {
+        RenderedDocument::placeholder(Vec::new(), width)
+    }
+
+    #[test]
+    fn evicts_the_least_recently_used() {
+        let mut cache = RenderCache::default();
+        // Paths that do not exist keep their `None` mtime
+        let path = |i: usize| PathBuf::from(format!("/nonexistent/md-explorer/{i}.md"));
+        for i in 0..MAX_ENTRIES {
+            cache.insert(&path(i), None, 80, OPTIONS, document(80));
+        }
+        assert!(cache.get(&path(0), 80, OPTIONS).is_some());
+        cache.insert(&path(MAX_ENTRIES), None, 80, OPTIONS, document(80));
+
+        assert_eq!(cache.entries.len(), MAX_ENTRIES);
+        assert!(cache.get(&path(0), 80, OPTIONS).is_some());
+        assert!(cache.get(&path(1), 80, OPTIONS).is_none());
+        assert!(cache.get(&path(2), 80, OPTIONS).is_some());
+        assert!(cache.get(&path(MAX_ENTRIES), 80, OPTIONS).is_some());
+    }
+
+    #[test]
+    fn keys_by_options_and_checks_width() {
+        let mut cache = RenderCache::default();
+        let path = Path::new("/nonexistent/md-explorer/options.md");
+        let raw = PreviewOptions {
+            raw: true,
+            ..OPTIONS
+        };
+        cache.insert(path, None, 80, OPTIONS, document(80));
+        cache.insert(path, None, 80, raw, document(80));
+
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.get(path, 80, raw).is_some());
+        assert!(cache.get(path, 60, OPTIONS).is_none());
+        assert!(cache.get_stale(path, 60, OPTIONS).is_none());
+    }
+
+    #[test]
+    fn invalidates_changed_files() {
+        let path =
+            std::env::temp_dir().join(format!("md-explorer-cache-{}.md", std::process::id()));
+        fs::write(&path, "# Cached\n").unwrap();
+        let mut cache = RenderCache::default();
+        cache.insert(&path, modified(&path), 80, OPTIONS, document(80));
+        assert!(cache.get(&path, 80, OPTIONS).is_some());
+        assert!(!cache.is_stale(&path, OPTIONS));
+
+        let later = modified(&path).unwrap() + Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        let stale = cache.is_stale(&path, OPTIONS);
+        let fresh = cache.get(&path, 80, OPTIONS);
+        let kept = cache.get_stale(&path, 80, OPTIONS);
+        fs::remove_file(&path).unwrap();
+
+        assert!(stale);
+        assert!(fresh.is_none());
+        assert!(kept.is_some());
+    }
+}
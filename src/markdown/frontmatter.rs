@@ -0,0 +1,230 @@
+use std::fs;
+use std::path::Path;
+
+use yaml_rust2::{Yaml, YamlLoader};
+
+/// Metadata from a document's leading `---` YAML or `+++` TOML block,
+/// flattened to display strings in the order written.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Frontmatter {
+    pub fields: Vec<(String, String)>,
+}
+
+impl Frontmatter {
+    /// Value of `key`, ignoring case.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Whether field `key` contains `value`, ignoring case.
+    pub fn matches(&self, key: &str, value: &str) -> bool {
+        self.get(key)
+            .is_some_and(|v| v.to_lowercase().contains(&value.to_lowercase()))
+    }
+}
+
+pub fn parse_yaml(text: &str) -> Result<Frontmatter, String> {
+    let docs = YamlLoader::load_from_str(text).map_err(|e| e.to_string())?;
+    let fields = match docs.into_iter().next() {
+        Some(Yaml::Hash(hash)) => hash
+            .into_iter()
+            .map(|(key, value)| (yaml_to_string(&key), yaml_to_string(&value)))
+            .collect(),
+        None | Some(Yaml::Null) => Vec::new(),
+        Some(_) => return Err("frontmatter is not a key/value mapping".to_string()),
+    };
+    Ok(Frontmatter { fields })
+}
+
+pub fn parse_toml(text: &str) -> Result<Frontmatter, String> {
+    let table: toml::Table = text
+        .parse()
+        .map_err(|e: toml::de::Error| e.message().to_string())?;
+    let fields = table
+        .iter()
+        .map(|(key, value)| (key.clone(), toml_to_string(value)))
+        .collect();
+    Ok(Frontmatter { fields })
+}
+
+/// Frontmatter at the top of `content`, if it has a valid block.
+pub fn extract_frontmatter(content: &str) -> Option<Frontmatter> {
+    let mut lines = content.lines();
+    let fence = lines.next()?.trim_end();
+    if fence != "---" && fence != "+++" {
+        return None;
+    }
+
+    let mut block = String::new();
+    for line in lines {
+        if line.trim_end() == fence {
+            return if fence == "---" {
+                parse_yaml(&block).ok()
+            } else {
+                parse_toml(&block).ok()
+            };
+        }
+        block.push_str(line);
+        block.push('\n');
+    }
+    None
+}
+
+pub fn read_frontmatter(path: &Path) -> Option<Frontmatter> {
+    extract_frontmatter(&fs::read_to_string(path).ok()?)
+}
+
+/// Number of leading lines taken up by a `---` or `+++` frontmatter block,
+/// fences included, or 0 if the document has none.
+pub fn frontmatter_len(content: &str) -> usize {
+    let mut lines = content.lines();
+    let Some(fence) = lines
+        .next()
+        .map(str::trim_end)
+        .filter(|l| *l == "---" || *l == "+++")
+    else {
+        return 0;
+    };
+    lines
+        .position(|l| l.trim_end() == fence)
+        .map_or(0, |end| end + 2)
+}
+
+/// The lines of `content` after its frontmatter, as `(line number, line,
+/// in_code)`. `in_code` is set for fenced code blocks, fences included; a
+/// fence left open runs to the end of the document.
+pub fn body_lines(content: &str) -> impl Iterator<Item = (usize, &str, bool)> {
+    let mut open: Option<CodeFence> = None;
+    content
+        .lines()
+        .enumerate()
+        .skip(frontmatter_len(content))
+        .map(move |(i, line)| match &open {
+            Some(fence) => {
+                if fence.is_closed_by(line) {
+                    open = None;
+                }
+                (i, line, true)
+            }
+            None => {
+                open = CodeFence::open(line);
+                (i, line, open.is_some())
+            }
+        })
+}
+
+/// The opening fence of a fenced code block.
+struct CodeFence {
+    marker: char,
+    len: usize,
+}
+
+impl CodeFence {
+    /// A run of three or more backticks or tildes, indented less than four
+    /// spaces. Backtick fences can't have backticks in their info string.
+    fn open(line: &str) -> Option<Self> {
+        let trimmed = line.trim_start_matches(' ');
+        if line.len() - trimmed.len() >= 4 {
+            return None;
+        }
+        let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+        let len = trimmed.len() - trimmed.trim_start_matches(marker).len();
+        (len >= 3 && !(marker == '`' && trimmed[len..].contains('`')))
+            .then_some(Self { marker, len })
+    }
+
+    /// Only a bare run of at least as many of the same character closes a fence.
+    fn is_closed_by(&self, line: &str) -> bool {
+        let trimmed = line.trim_start_matches(' ');
+        let len = trimmed.len() - trimmed.trim_start_matches(self.marker).len();
+        line.len() - trimmed.len() < 4 && len >= self.len && trimmed[len..].trim().is_empty()
+    }
+}
+
+fn yaml_to_string(value: &Yaml) -> String {
+    match value {
+        Yaml::String(s) | Yaml::Real(s) => s.clone(),
+        Yaml::Integer(i) => i.to_string(),
+        Yaml::Boolean(b) => b.to_string(),
+        Yaml::Array(items) => items
+            .iter()
+            .map(yaml_to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        Yaml::Hash(hash) => hash
+            .iter()
+            .map(|(k, v)| format!("{}: {}", yaml_to_string(k), yaml_to_string(v)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Yaml::Alias(_) | Yaml::Null | Yaml::BadValue => String::new(),
+    }
+}
+
+fn toml_to_string(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(s) => s.clone(),
+        toml::Value::Array(items) => items
+            .iter()
+            .map(toml_to_string)
+            .collect::<Vec<_>>()
+            .join(", "),
+        toml::Value::Table(table) => table
+            .iter()
+            .map(|(k, v)| format!("{}: {}", k, toml_to_string(v)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        toml::Value::Integer(i) => i.to_string(),
+        toml::Value::Float(f) => f.to_string(),
+        toml::Value::Boolean(b) => b.to_string(),
+        toml::Value::Datetime(d) => d.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code_flags(content: &str) -> Vec<bool> {
+        body_lines(content).map(|(_, _, in_code)| in_code).collect()
+    }
+
+    #[test]
+    fn frontmatter_is_skipped() {
+        let content = "---\ntitle: x\n---\n# Body\n";
+        assert_eq!(frontmatter_len(content), 3);
+        let lines: Vec<(usize, &str, bool)> = body_lines(content).collect();
+        assert_eq!(lines, vec![(3, "# Body", false)]);
+    }
+
+    #[test]
+    fn unclosed_frontmatter_is_body() {
+        assert_eq!(frontmatter_len("---\ntitle: x\n"), 0);
+        assert_eq!(frontmatter_len("text\n---\n"), 0);
+    }
+
+    #[test]
+    fn longer_fence_is_not_closed_by_shorter() {
+        let content = "````\n```\ncode\n````\ntext";
+        assert_eq!(code_flags(content), vec![true, true, true, true, false]);
+    }
+
+    #[test]
+    fn fence_needs_same_marker_and_bare_close() {
+        let content = "~~~\n```\n~~~ not a close\n~~~\ntext";
+        assert_eq!(code_flags(content), vec![true, true, true, true, false]);
+    }
+
+    #[test]
+    fn indented_and_inline_backticks_do_not_open() {
+        let content = "    ```\n``` a ` b\ntext";
+        assert_eq!(code_flags(content), vec![false, false, false]);
+    }
+
+    #[test]
+    fn unclosed_fence_runs_to_end() {
+        assert_eq!(code_flags("```\na\nb"), vec![true, true, true]);
+    }
+}
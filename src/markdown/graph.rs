@@ -0,0 +1,343 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::SystemTime;
+
+use pulldown_cmark::{Event, LinkType, Parser, Tag};
+
+use crate::markdown::links::{resolve_link, resolve_wikilink, LinkRef, LinkTarget, WikiIndex};
+use crate::markdown::render::parser_options;
+
+/// Longest context snippet kept for a backlink, in characters.
+const SNIPPET_LENGTH: usize = 120;
+
+/// A link to a document from another file in the corpus.
+#[derive(Debug, Clone)]
+pub struct Backlink {
+    pub source: PathBuf,
+    /// Zero-based line of the link in `source`
+    pub line: usize,
+    /// The linking line, trimmed, for context
+    pub snippet: String,
+}
+
+/// Which documents link to which, over relative links and wikilinks.
+///
+/// Each source is re-read only when its mtime changes, so keeping the graph
+/// current costs one `stat` per file, like the search index.
+#[derive(Debug, Clone, Default)]
+pub struct LinkGraph {
+    /// Outgoing links of each source, as (target, backlink), in file order
+    sources: HashMap<PathBuf, SourceLinks>,
+    /// Sources in scan order, so backlinks list in file order
+    order: Vec<PathBuf>,
+    backlinks: HashMap<PathBuf, Vec<Backlink>>,
+}
+
+#[derive(Debug, Clone)]
+struct SourceLinks {
+    mtime: Option<SystemTime>,
+    links: Vec<(PathBuf, Backlink)>,
+}
+
+impl LinkGraph {
+    /// Whether `files` differ from the sources, or any of them changed on
+    /// disk since it was read.
+    pub fn is_stale(&self, files: &[PathBuf]) -> bool {
+        self.order != files
+            || files.iter().any(|source| {
+                let mtime = fs::metadata(source).and_then(|m| m.modified()).ok();
+                self.sources
+                    .get(source)
+                    .is_none_or(|known| known.mtime != mtime)
+            })
+    }
+
+    /// Bring the graph in line with `files`, re-reading new and modified
+    /// sources. When files were added or removed every source is re-read,
+    /// as its wikilinks may now resolve elsewhere. Returns whether anything
+    /// changed.
+    pub fn update(&mut self, files: &[PathBuf], roots: &[PathBuf], wiki: &WikiIndex) -> bool {
+        let reread_all = self.order != files;
+        let mut changed = reread_all;
+        if reread_all {
+            self.sources.clear();
+            self.order = files.to_vec();
+        }
+
+        for source in files {
+            let mtime = fs::metadata(source).and_then(|m| m.modified()).ok();
+            if !reread_all
+                && self
+                    .sources
+                    .get(source)
+                    .is_some_and(|known| known.mtime == mtime)
+            {
+                continue;
+            }
+            let links = fs::read_to_string(source)
+                .map(|content| source_links(source, &content, roots, wiki))
+                .unwrap_or_default();
+            self.sources
+                .insert(source.clone(), SourceLinks { mtime, links });
+            changed = true;
+        }
+
+        if changed {
+            self.backlinks.clear();
+            for source in &self.order {
+                for (target, backlink) in self.sources.get(source).map_or(&[][..], |s| &s.links) {
+                    self.backlinks
+                        .entry(target.clone())
+                        .or_default()
+                        .push(backlink.clone());
+                }
+            }
+        }
+        changed
+    }
+
+    /// Links to `path`, in file order.
+    pub fn backlinks(&self, path: &Path) -> &[Backlink] {
+        self.backlinks
+            .get(path)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Bring `graph` up to date with `files` on a background thread, or build
+/// it if it is `None`. Sends back the new graph, or `None` if nothing changed.
+pub fn update_in_background(
+    graph: Option<Arc<LinkGraph>>,
+    files: Vec<PathBuf>,
+    roots: Vec<PathBuf>,
+    wiki: Arc<WikiIndex>,
+) -> Receiver<Option<Arc<LinkGraph>>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let updated = match graph {
+            Some(graph) if !graph.is_stale(&files) => None,
+            graph => {
+                let mut graph = graph.map(|g| (*g).clone()).unwrap_or_default();
+                graph.update(&files, &roots, &wiki);
+                Some(Arc::new(graph))
+            }
+        };
+        let _ = tx.send(updated);
+    });
+    rx
+}
+
+/// The links of `source` that point at other files, with their backlink.
+fn source_links(
+    source: &Path,
+    content: &str,
+    roots: &[PathBuf],
+    wiki: &WikiIndex,
+) -> Vec<(PathBuf, Backlink)> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut links: Vec<(PathBuf, Backlink)> = Vec::new();
+    for (link, line) in extract_links(content) {
+        let target = match &link {
+            LinkRef::Markdown(dest) => Some(resolve_link(source, dest, roots)),
+            LinkRef::Wiki(dest) => resolve_wikilink(source, dest, wiki),
+        };
+        // Anchors within the same file are not backlinks
+        let Some(LinkTarget::Local {
+            path: Some(target), ..
+        }) = target
+        else {
+            continue;
+        };
+        if target == source {
+            continue;
+        }
+        // One backlink per line for each target
+        if links.iter().any(|(t, b)| *t == target && b.line == line) {
+            continue;
+        }
+        let snippet = lines
+            .get(line)
+            .map(|l| l.trim().chars().take(SNIPPET_LENGTH).collect())
+            .unwrap_or_default();
+        links.push((
+            target,
+            Backlink {
+                source: source.to_path_buf(),
+                line,
+                snippet,
+            },
+        ));
+    }
+    links
+}
+
+/// Every link in `content` with the zero-based source line it starts on.
+/// Image embeds are skipped.
+pub fn extract_links(content: &str) -> Vec<(LinkRef, usize)> {
+    let mut links = Vec::new();
+    let mut line = 0;
+    let mut counted = 0;
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        let Event::Start(Tag::Link {
+            link_type,
+            dest_url,
+            ..
+        }) = event
+        else {
+            continue;
+        };
+        line += content[counted..range.start].matches('\n').count();
+        counted = range.start;
+
+        let link = match link_type {
+            LinkType::WikiLink { .. } => LinkRef::Wiki(dest_url.to_string()),
+            _ => LinkRef::Markdown(dest_url.to_string()),
+        };
+        links.push((link, line));
+    }
+    links
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    /// A directory of markdown files in the temp dir, removed on drop.
+    struct Notes(PathBuf);
+
+    impl Notes {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "md-explorer-graph-{}-{}",
+                std::process::id(),
+                name
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            let notes = Self(dir);
+            for (file, content) in files {
+                notes.write(file, content);
+            }
+            notes
+        }
+
+        fn path(&self, file: &str) -> PathBuf {
+            self.0.join(file)
+        }
+
+        /// Write `file`, moving its mtime on so the change is seen even
+        /// within the filesystem's timestamp resolution.
+        fn write(&self, file: &str, content: &str) {
+            let path = self.path(file);
+            let mtime = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .map_or(SystemTime::now(), |t| t + Duration::from_secs(5));
+            fs::write(&path, content).unwrap();
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(mtime)
+                .unwrap();
+        }
+
+        fn files(&self, names: &[&str]) -> Vec<PathBuf> {
+            names.iter().map(|name| self.path(name)).collect()
+        }
+
+        fn update(&self, graph: &mut LinkGraph, names: &[&str]) -> bool {
+            let files = self.files(names);
+            graph.update(&files, &[], &WikiIndex::new(&files))
+        }
+
+        fn sources(&self, graph: &LinkGraph, file: &str) -> Vec<(String, usize)> {
+            graph
+                .backlinks(&self.path(file))
+                .iter()
+                .map(|b| {
+                    let name = b.source.file_name().unwrap().to_string_lossy().to_string();
+                    (name, b.line)
+                })
+                .collect()
+        }
+    }
+
+    impl Drop for Notes {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn extracts_links_with_their_lines() {
+        let links =
+            extract_links("[a](a.md)\n\n```\n[no](x.md)\n```\nsee [[b|bee]] ![img](i.png)\n");
+        assert_eq!(
+            links,
+            [
+                (LinkRef::Markdown("a.md".to_string()), 0),
+                (LinkRef::Wiki("b".to_string()), 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn collects_backlinks_in_file_order() {
+        let notes = Notes::new(
+            "order",
+            &[
+                (
+                    "a.md",
+                    "Links to [[c]] and [c](c.md) on one line\n\n[self](#top) [me](a.md)\n",
+                ),
+                ("b.md", "# B\n\nSee [[C#Intro]]\n"),
+                ("c.md", "# C\n"),
+            ],
+        );
+        let mut graph = LinkGraph::default();
+        assert!(notes.update(&mut graph, &["a.md", "b.md", "c.md"]));
+        assert_eq!(
+            notes.sources(&graph, "c.md"),
+            [("a.md".to_string(), 0), ("b.md".to_string(), 2)]
+        );
+        assert_eq!(
+            graph.backlinks(&notes.path("c.md"))[1].snippet,
+            "See [[C#Intro]]"
+        );
+        // Links within a file are not backlinks
+        assert!(graph.backlinks(&notes.path("a.md")).is_empty());
+    }
+
+    #[test]
+    fn updates_edited_and_removed_files() {
+        let notes = Notes::new(
+            "update",
+            &[
+                ("a.md", "[[c]]\n"),
+                ("b.md", "nothing\n"),
+                ("c.md", "# C\n"),
+            ],
+        );
+        let names = ["a.md", "b.md", "c.md"];
+        let mut graph = LinkGraph::default();
+        notes.update(&mut graph, &names);
+        assert!(!graph.is_stale(&notes.files(&names)));
+        assert!(!notes.update(&mut graph, &names));
+
+        notes.write("b.md", "now [[c]]\n");
+        assert!(graph.is_stale(&notes.files(&names)));
+        assert!(notes.update(&mut graph, &names));
+        assert_eq!(
+            notes.sources(&graph, "c.md"),
+            [("a.md".to_string(), 0), ("b.md".to_string(), 0)]
+        );
+
+        assert!(notes.update(&mut graph, &["b.md", "c.md"]));
+        assert_eq!(notes.sources(&graph, "c.md"), [("b.md".to_string(), 0)]);
+    }
+}
@@ -0,0 +1,139 @@
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+use pulldown_cmark::{Event, Parser, Tag, TagEnd};
+
+use crate::markdown::render::parser_options;
+
+/// A heading found in a markdown source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    pub level: u8,
+    /// Plain text without inline markup
+    pub text: String,
+    /// Zero-based source line of the heading text
+    pub line: usize,
+}
+
+/// A heading together with the file it belongs to, for corpus-wide heading search.
+#[derive(Debug, Clone)]
+pub struct HeadingEntry {
+    pub path: PathBuf,
+    pub heading: Heading,
+}
+
+/// Extract every heading the preview would render, ATX and setext alike,
+/// skipping code blocks and frontmatter.
+pub fn extract_headings(content: &str) -> Vec<Heading> {
+    let mut headings = Vec::new();
+    let mut current: Option<Heading> = None;
+    let mut line = 0;
+    let mut counted = 0;
+    for (event, range) in Parser::new_ext(content, parser_options()).into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                line += content[counted..range.start].matches('\n').count();
+                counted = range.start;
+                current = Some(Heading {
+                    level: level as u8,
+                    text: String::new(),
+                    line,
+                });
+            }
+            Event::End(TagEnd::Heading(_)) => {
+                if let Some(mut heading) = current.take() {
+                    heading.text = heading.text.trim().to_string();
+                    headings.push(heading);
+                }
+            }
+            Event::Text(text) | Event::Code(text) | Event::InlineMath(text) => {
+                if let Some(heading) = &mut current {
+                    heading.text.push_str(&text);
+                }
+            }
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(heading) = &mut current {
+                    heading.text.push(' ');
+                }
+            }
+            _ => {}
+        }
+    }
+    headings
+}
+
+/// Read and extract the headings of `files` on a background thread, so
+/// opening heading search does not block input.
+pub fn index_in_background(files: Vec<PathBuf>) -> Receiver<Vec<HeadingEntry>> {
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+        let entries = files
+            .into_iter()
+            .filter_map(|path| {
+                let content = std::fs::read_to_string(&path).ok()?;
+                Some(
+                    extract_headings(&content)
+                        .into_iter()
+                        .map(|heading| HeadingEntry {
+                            path: path.clone(),
+                            heading,
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .flatten()
+            .collect();
+        let _ = tx.send(entries);
+    });
+    rx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headings(content: &str) -> Vec<(u8, String, usize)> {
+        extract_headings(content)
+            .into_iter()
+            .map(|heading| (heading.level, heading.text, heading.line))
+            .collect()
+    }
+
+    #[test]
+    fn finds_atx_and_setext_headings() {
+        let content =
+            "---\ntitle: x\n---\n# One #\n\nTwo\nlines\n===\n\n### Three ##\n\nFour\n----\n";
+        assert_eq!(
+            headings(content),
+            [
+                (1, "One".to_string(), 3),
+                (1, "Two lines".to_string(), 5),
+                (3, "Three".to_string(), 9),
+                (2, "Four".to_string(), 11),
+            ]
+        );
+    }
+
+    #[test]
+    fn keeps_hashes_that_do_not_close() {
+        assert_eq!(
+            headings("# C#\n## Issue #12\n"),
+            [(1, "C#".to_string(), 0), (2, "Issue #12".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn strips_inline_markup() {
+        assert_eq!(
+            headings("## The **`render`** [call](x.md)\n"),
+            [(2, "The render call".to_string(), 0)]
+        );
+    }
+
+    #[test]
+    fn skips_code_and_list_underlines() {
+        let content = "```\n# not a heading\n```\n\n~~~~\n```\n# still code\n~~~~\n\n- item\n---\n\n    # indented\n";
+        assert!(headings(content).is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::OnceLock;
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+use crate::theme::Theme as AppTheme;
+
+/// Bundled themes for the app's dark and light themes. Their backgrounds are
+/// ignored so code blocks sit on the terminal background like the rest of
+/// the preview.
+const DARK_CODE_THEME: &str = "base16-ocean.dark";
+const LIGHT_CODE_THEME: &str = "base16-ocean.light";
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| {
+        let name = match AppTheme::current() {
+            AppTheme::Dark => DARK_CODE_THEME,
+            AppTheme::Light => LIGHT_CODE_THEME,
+        };
+        let mut themes = ThemeSet::load_defaults().themes;
+        themes.remove(name).unwrap_or_default()
+    })
+}
+
+/// Highlight `code` using the grammar named by a fence info string such as
+/// `rust`, `bash` or `yaml`. Returns `None` for unknown languages so the
+/// caller can fall back to plain text.
+pub fn highlight_code(code: &str, language: &str) -> Option<Vec<Vec<Span<'static>>>> {
+    if language.is_empty() {
+        return None;
+    }
+
+    let syntaxes = syntax_set();
+    let syntax = syntaxes.find_syntax_by_token(language)?;
+    let mut highlighter = HighlightLines::new(syntax, theme());
+
+    let mut lines = Vec::new();
+    for line in LinesWithEndings::from(code) {
+        let regions = highlighter.highlight_line(line, syntaxes).ok()?;
+        let spans = regions
+            .into_iter()
+            .map(|(style, text)| {
+                let mut span_style = Style::default().fg(Color::Rgb(
+                    style.foreground.r,
+                    style.foreground.g,
+                    style.foreground.b,
+                ));
+                if style.font_style.contains(FontStyle::BOLD) {
+                    span_style = span_style.add_modifier(Modifier::BOLD);
+                }
+                if style.font_style.contains(FontStyle::ITALIC) {
+                    span_style = span_style.add_modifier(Modifier::ITALIC);
+                }
+                if style.font_style.contains(FontStyle::UNDERLINE) {
+                    span_style = span_style.add_modifier(Modifier::UNDERLINED);
+                }
+                Span::styled(text.trim_end_matches(['\n', '\r']).to_string(), span_style)
+            })
+            .filter(|span| !span.content.is_empty())
+            .collect();
+        lines.push(spans);
+    }
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highlights_known_languages() {
+        let lines = highlight_code("let x = 1;\nlet y = 2;", "rust").unwrap();
+        assert_eq!(lines.len(), 2);
+        let text: String = lines[0].iter().map(|span| span.content.as_ref()).collect();
+        assert_eq!(text, "let x = 1;");
+        assert!(lines[0].len() > 1);
+    }
+
+    #[test]
+    fn unknown_languages_fall_back() {
+        assert!(highlight_code("x", "").is_none());
+        assert!(highlight_code("x", "no-such-language").is_none());
+    }
+}
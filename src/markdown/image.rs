@@ -0,0 +1,445 @@
+use std::fmt::Write as _;
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader, RgbaImage};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+/// Environment variable forcing an image protocol: `kitty`, `iterm`,
+/// `sixel`, `halfblocks`, or `off` to start with images hidden.
+const PROTOCOL_VAR: &str = "MD_EXPLORER_IMAGES";
+
+/// Images larger than this on disk are not decoded.
+const MAX_FILE_SIZE: u64 = 20 * 1024 * 1024;
+
+/// Tallest an image may be drawn, in terminal rows.
+const MAX_ROWS: u32 = 24;
+
+/// Bytes of base64 per kitty graphics escape sequence.
+const KITTY_CHUNK: usize = 4096;
+
+/// Kitty: delete every image on screen and free its data.
+pub const KITTY_DELETE_ALL: &str = "\x1b_Ga=d,d=A,q=2\x1b\\";
+
+/// Source of image ids, so kitty can place and delete each image on its own.
+static NEXT_IMAGE_ID: AtomicU32 = AtomicU32::new(1);
+
+/// How images are drawn in the preview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageProtocol {
+    Kitty,
+    Iterm,
+    Sixel,
+    /// Unicode `▀` cells coloured with two pixels each; works in any
+    /// truecolor terminal
+    Halfblocks,
+}
+
+impl ImageProtocol {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Kitty => "kitty",
+            Self::Iterm => "iTerm",
+            Self::Sixel => "sixel",
+            Self::Halfblocks => "halfblocks",
+        }
+    }
+}
+
+/// The detected protocol and the terminal's cell size in pixels, which
+/// decide how an image is scaled to rows and columns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageSettings {
+    pub protocol: ImageProtocol,
+    pub cell_width: u16,
+    pub cell_height: u16,
+}
+
+impl ImageSettings {
+    /// Detect the protocol from the environment. Returns the settings and
+    /// whether images should be shown at startup.
+    pub fn detect() -> (Self, bool) {
+        let forced = std::env::var(PROTOCOL_VAR).unwrap_or_default();
+        let protocol = match forced.to_lowercase().as_str() {
+            "kitty" => ImageProtocol::Kitty,
+            "iterm" => ImageProtocol::Iterm,
+            "sixel" => ImageProtocol::Sixel,
+            "halfblocks" | "off" => ImageProtocol::Halfblocks,
+            _ => detect_protocol(),
+        };
+
+        // Terminals that don't report pixel sizes get a typical 8x16 cell
+        let (cell_width, cell_height) = match crossterm::terminal::window_size() {
+            Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => {
+                (size.width / size.columns, size.height / size.rows)
+            }
+            _ => (8, 16),
+        };
+
+        let settings = Self {
+            protocol,
+            cell_width: cell_width.max(1),
+            cell_height: cell_height.max(1),
+        };
+        (settings, !forced.eq_ignore_ascii_case("off"))
+    }
+}
+
+fn detect_protocol() -> ImageProtocol {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    let term = var("TERM");
+    let program = var("TERM_PROGRAM");
+
+    if term.contains("kitty") || !var("KITTY_WINDOW_ID").is_empty() || program == "ghostty" {
+        ImageProtocol::Kitty
+    } else if matches!(program.as_str(), "iTerm.app" | "WezTerm") {
+        ImageProtocol::Iterm
+    } else if term.contains("sixel") || term.starts_with("foot") || term.starts_with("mlterm") {
+        ImageProtocol::Sixel
+    } else {
+        ImageProtocol::Halfblocks
+    }
+}
+
+/// A decoded image sized for the preview.
+pub struct InlineImage {
+    /// One line per row: the picture itself for halfblocks, otherwise blank
+    /// rows for the terminal to draw over
+    pub lines: Vec<Line<'static>>,
+    /// The image for graphics protocols to draw over the blank rows
+    pub graphics: Option<Arc<GraphicsImage>>,
+}
+
+/// An image drawn by a graphics protocol over `cols` x `rows` cells.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GraphicsImage {
+    /// Unique for the session; kitty keeps the image data under it
+    pub id: u32,
+    pub cols: u16,
+    pub rows: u16,
+    /// Escape sequence that draws the image at the cursor. For kitty it only
+    /// transmits the data, which [`GraphicsImage::place`] then shows.
+    pub sequence: String,
+}
+
+impl GraphicsImage {
+    /// Kitty: show the transmitted image at the cursor.
+    pub fn place(&self) -> String {
+        format!(
+            "\x1b_Ga=p,i={},c={},r={},C=1,q=2\x1b\\",
+            self.id, self.cols, self.rows
+        )
+    }
+
+    /// Kitty: take the image off the screen, freeing its data unless
+    /// `keep_data`.
+    pub fn delete(&self, keep_data: bool) -> String {
+        let what = if keep_data { 'i' } else { 'I' };
+        format!("\x1b_Ga=d,d={},i={},q=2\x1b\\", what, self.id)
+    }
+}
+
+/// Decode the image at `path` and scale it to at most `max_cols` columns.
+pub fn load_image(
+    path: &Path,
+    max_cols: u16,
+    settings: ImageSettings,
+) -> Result<InlineImage, String> {
+    let size = std::fs::metadata(path).map_err(|e| e.to_string())?.len();
+    if size > MAX_FILE_SIZE {
+        return Err("image too large".to_string());
+    }
+    let image = ImageReader::open(path)
+        .and_then(|reader| reader.with_guessed_format())
+        .map_err(|e| e.to_string())?
+        .decode()
+        .map_err(|e| e.to_string())?;
+    let (cols, rows) = fit_cells(image.width(), image.height(), max_cols, settings);
+    let scaled = || {
+        image.resize(
+            cols as u32 * settings.cell_width as u32,
+            rows as u32 * settings.cell_height as u32,
+            FilterType::Triangle,
+        )
+    };
+    let id = NEXT_IMAGE_ID.fetch_add(1, Ordering::Relaxed);
+    let sequence = match settings.protocol {
+        ImageProtocol::Halfblocks => {
+            let pixels = image
+                .resize_exact(cols as u32, rows as u32 * 2, FilterType::Triangle)
+                .to_rgba8();
+            return Ok(InlineImage {
+                lines: halfblock_lines(&pixels),
+                graphics: None,
+            });
+        }
+        ImageProtocol::Kitty => kitty_sequence(&scaled().to_rgba8(), id),
+        ImageProtocol::Iterm => iterm_sequence(&scaled(), cols, rows)?,
+        ImageProtocol::Sixel => sixel_sequence(&scaled().to_rgba8()),
+    };
+    Ok(InlineImage {
+        lines: vec![Line::default(); rows as usize],
+        graphics: Some(Arc::new(GraphicsImage {
+            id,
+            cols,
+            rows,
+            sequence,
+        })),
+    })
+}
+
+/// Columns and rows an image of `width` x `height` pixels takes up: its
+/// natural size, narrowed to `max_cols` and then to `MAX_ROWS`, keeping the
+/// aspect ratio.
+fn fit_cells(width: u32, height: u32, max_cols: u16, settings: ImageSettings) -> (u16, u16) {
+    let (width, height) = (width.max(1), height.max(1));
+    let cell_w = settings.cell_width as u32;
+    let cell_h = settings.cell_height as u32;
+    let mut cols = width.div_ceil(cell_w).clamp(1, max_cols.max(1) as u32);
+    let mut rows = (cols * cell_w * height).div_ceil(width * cell_h).max(1);
+    if rows > MAX_ROWS {
+        rows = MAX_ROWS;
+        cols = (rows * cell_h * width / (height * cell_w)).max(1);
+    }
+    (cols as u16, rows as u16)
+}
+
+/// Two pixels per cell: the upper one as the `▀` foreground, the lower one
+/// as the background. Transparent pixels show the terminal background.
+fn halfblock_lines(pixels: &RgbaImage) -> Vec<Line<'static>> {
+    let color = |x: u32, y: u32| {
+        let [r, g, b, a] = pixels.get_pixel(x, y).0;
+        if a < 128 {
+            Color::Reset
+        } else {
+            Color::Rgb(r, g, b)
+        }
+    };
+    (0..pixels.height() / 2)
+        .map(|row| {
+            let spans: Vec<Span> = (0..pixels.width())
+                .map(|x| {
+                    Span::styled(
+                        "▀",
+                        Style::default()
+                            .fg(color(x, row * 2))
+                            .bg(color(x, row * 2 + 1)),
+                    )
+                })
+                .collect();
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// Kitty graphics protocol: raw RGBA, sent in chunks and kept by the
+/// terminal under `id` until deleted.
+fn kitty_sequence(pixels: &RgbaImage, id: u32) -> String {
+    let data = STANDARD.encode(pixels.as_raw());
+    let chunks: Vec<&str> = data
+        .as_bytes()
+        .chunks(KITTY_CHUNK)
+        .map(|chunk| std::str::from_utf8(chunk).unwrap_or_default())
+        .collect();
+
+    let mut out = String::new();
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = u8::from(i + 1 < chunks.len());
+        if i == 0 {
+            let _ = write!(
+                out,
+                "\x1b_Ga=t,f=32,s={},v={},i={},q=2,m={};{}\x1b\\",
+                pixels.width(),
+                pixels.height(),
+                id,
+                more,
+                chunk
+            );
+        } else {
+            let _ = write!(out, "\x1b_Gm={};{}\x1b\\", more, chunk);
+        }
+    }
+    out
+}
+
+/// iTerm2 inline image protocol, also understood by WezTerm: a base64 PNG.
+fn iterm_sequence(image: &DynamicImage, cols: u16, rows: u16) -> Result<String, String> {
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(format!(
+        "\x1b]1337;File=inline=1;size={};width={};height={};preserveAspectRatio=1:{}\x07",
+        png.len(),
+        cols,
+        rows,
+        STANDARD.encode(&png)
+    ))
+}
+
+/// Sixel graphics with a fixed 6x6x6 colour cube. Transparent pixels are
+/// left unpainted.
+fn sixel_sequence(pixels: &RgbaImage) -> String {
+    let level = |v: u8| (v as u16 * 5 + 127) / 255;
+    let index = |x: u32, y: u32| {
+        let [r, g, b, a] = pixels.get_pixel(x, y).0;
+        (a >= 128).then(|| (level(r) * 36 + level(g) * 6 + level(b)) as usize)
+    };
+
+    let (width, height) = pixels.dimensions();
+    let mut out = format!("\x1bP0;1;0q\"1;1;{};{}", width, height);
+    for i in 0..216u16 {
+        let percent = |v: u16| v * 100 / 5;
+        let _ = write!(
+            out,
+            "#{};2;{};{};{}",
+            i,
+            percent(i / 36),
+            percent(i / 6 % 6),
+            percent(i % 6)
+        );
+    }
+
+    for band in (0..height).step_by(6) {
+        // Six pixel rows at a time, one pass per colour used in the band
+        let mut masks = vec![vec![0u8; width as usize]; 216];
+        let mut used = [false; 216];
+        for dy in 0..6.min(height - band) {
+            for x in 0..width {
+                if let Some(color) = index(x, band + dy) {
+                    masks[color][x as usize] |= 1 << dy;
+                    used[color] = true;
+                }
+            }
+        }
+        for (color, mask) in masks.iter().enumerate().filter(|(c, _)| used[*c]) {
+            let _ = write!(out, "#{}", color);
+            push_sixel_run(&mut out, mask);
+            out.push('$');
+        }
+        out.push('-');
+    }
+    out.push_str("\x1b\\");
+    out
+}
+
+/// Append one colour's sixels for a band, run-length encoded.
+fn push_sixel_run(out: &mut String, mask: &[u8]) {
+    let mut i = 0;
+    while i < mask.len() {
+        let run = mask[i..].iter().take_while(|&&m| m == mask[i]).count();
+        let c = (63 + mask[i]) as char;
+        if run > 3 {
+            let _ = write!(out, "!{}{}", run, c);
+        } else {
+            out.extend(std::iter::repeat_n(c, run));
+        }
+        i += run;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::Rgba;
+
+    const SETTINGS: ImageSettings = ImageSettings {
+        protocol: ImageProtocol::Kitty,
+        cell_width: 8,
+        cell_height: 16,
+    };
+
+    #[test]
+    fn fits_images_to_cells() {
+        // Natural size, rounded up to whole cells
+        assert_eq!(fit_cells(80, 48, 100, SETTINGS), (10, 3));
+        assert_eq!(fit_cells(1, 1, 100, SETTINGS), (1, 1));
+        // Narrowed to the pane, keeping the aspect ratio
+        assert_eq!(fit_cells(1600, 320, 50, SETTINGS), (50, 5));
+        // Tall images are capped at MAX_ROWS
+        assert_eq!(fit_cells(160, 1600, 100, SETTINGS), (4, MAX_ROWS as u16));
+        assert_eq!(fit_cells(1, 100_000, 100, SETTINGS), (1, MAX_ROWS as u16));
+        assert_eq!(fit_cells(0, 0, 0, SETTINGS), (1, 1));
+    }
+
+    #[test]
+    fn halfblocks_pair_pixel_rows() {
+        let mut pixels = RgbaImage::new(2, 4);
+        pixels.put_pixel(0, 0, Rgba([255, 0, 0, 255]));
+        pixels.put_pixel(0, 1, Rgba([0, 0, 255, 255]));
+        pixels.put_pixel(1, 3, Rgba([0, 255, 0, 255]));
+        let lines = halfblock_lines(&pixels);
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.spans.len() == 2));
+        let style = |row: usize, col: usize| {
+            let style = lines[row].spans[col].style;
+            (style.fg, style.bg)
+        };
+        assert_eq!(
+            style(0, 0),
+            (Some(Color::Rgb(255, 0, 0)), Some(Color::Rgb(0, 0, 255)))
+        );
+        // Transparent pixels show the terminal background
+        assert_eq!(style(0, 1), (Some(Color::Reset), Some(Color::Reset)));
+        assert_eq!(
+            style(1, 1),
+            (Some(Color::Reset), Some(Color::Rgb(0, 255, 0)))
+        );
+    }
+
+    #[test]
+    fn kitty_data_is_sent_in_chunks() {
+        let pixels = RgbaImage::from_pixel(40, 40, Rgba([1, 2, 3, 255]));
+        let sequence = kitty_sequence(&pixels, 7);
+        let escapes: Vec<&str> = sequence
+            .strip_suffix("\x1b\\")
+            .unwrap()
+            .split("\x1b\\")
+            .collect();
+        assert_eq!(escapes.len(), 3);
+        assert!(escapes[0].starts_with("\x1b_Ga=t,f=32,s=40,v=40,i=7,q=2,m=1;"));
+        assert!(escapes[1].starts_with("\x1b_Gm=1;"));
+        assert!(escapes[2].starts_with("\x1b_Gm=0;"));
+
+        let data: String = escapes
+            .iter()
+            .map(|escape| {
+                let (_, chunk) = escape.split_once(';').unwrap();
+                assert!(chunk.len() <= KITTY_CHUNK);
+                chunk
+            })
+            .collect();
+        assert_eq!(STANDARD.decode(data).unwrap(), pixels.as_raw().as_slice());
+    }
+
+    #[test]
+    fn sixels_are_framed_in_bands() {
+        let mut pixels = RgbaImage::from_pixel(3, 7, Rgba([255, 255, 255, 255]));
+        pixels.put_pixel(2, 6, Rgba([0, 0, 0, 0]));
+        let sequence = sixel_sequence(&pixels);
+        assert!(sequence.starts_with("\x1bP0;1;0q\"1;1;3;7#0;2;0;0;0"));
+        assert!(sequence.ends_with("-\x1b\\"));
+
+        let bands: Vec<&str> = sequence
+            .split_once("#215;2;100;100;100")
+            .unwrap()
+            .1
+            .split('-')
+            .collect();
+        // Two bands, then the terminator
+        assert_eq!(bands, ["#215~~~$", "#215@@?$", "\x1b\\"]);
+    }
+
+    #[test]
+    fn sixel_runs_are_compressed() {
+        let mut out = String::new();
+        push_sixel_run(&mut out, &[1, 1, 1, 1, 2, 2, 0]);
+        assert_eq!(out, "!4@AA?");
+    }
+}
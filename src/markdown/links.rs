@@ -0,0 +1,344 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use crate::markdown::headings::extract_headings;
+
+/// A link as written in a document, before it is resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkRef {
+    /// `[text](dest)`, resolved relative to the linking file
+    Markdown(String),
+    /// `[[page#heading|alias]]`, resolved by filename stem; holds `page#heading`
+    Wiki(String),
+}
+
+/// Where a link in a document points.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// A URL with a scheme, such as `https:` or `mailto:`; never opened
+    External(String),
+    /// A file relative to the linking document, with an optional `#anchor`.
+    /// `path` is `None` for anchors within the same document
+    Local {
+        path: Option<PathBuf>,
+        anchor: Option<String>,
+    },
+}
+
+/// Resolve a link destination as written in `from`. Root-relative
+/// destinations such as `/docs/setup.md` are taken relative to whichever of
+/// the scanned `roots` contains `from`.
+pub fn resolve_link(from: &Path, dest: &str, roots: &[PathBuf]) -> LinkTarget {
+    if has_scheme(dest) {
+        return LinkTarget::External(dest.to_string());
+    }
+
+    let (file, anchor) = match dest.split_once('#') {
+        Some((file, anchor)) => (file, Some(percent_decode(anchor))),
+        None => (dest, None),
+    };
+    let anchor = anchor.filter(|a| !a.is_empty());
+
+    if file.is_empty() {
+        return LinkTarget::Local { path: None, anchor };
+    }
+
+    let file = percent_decode(file);
+    let base = from.parent().unwrap_or(Path::new(""));
+    let path = if let Some(rest) = file.strip_prefix('/') {
+        // Outside the scanned roots only the filesystem root is left
+        let root = roots
+            .iter()
+            .filter(|root| from.starts_with(root))
+            .max_by_key(|root| root.components().count())
+            .map_or(Path::new("/"), PathBuf::as_path);
+        root.join(rest)
+    } else {
+        base.join(file)
+    };
+
+    LinkTarget::Local {
+        path: Some(normalize(&path)),
+        anchor,
+    }
+}
+
+/// Resolve a wikilink destination such as `page`, `notes/page#Heading` or
+/// `#Heading`. Returns `None` when no scanned file has a matching name.
+pub fn resolve_wikilink(from: &Path, dest: &str, wiki: &WikiIndex) -> Option<LinkTarget> {
+    let (page, anchor) = match dest.split_once('#') {
+        Some((page, anchor)) => (page, Some(slugify(anchor))),
+        None => (dest, None),
+    };
+    let anchor = anchor.filter(|a| !a.is_empty());
+
+    let path = if page.trim().is_empty() {
+        None
+    } else {
+        Some(wiki.resolve(from, page.trim())?.to_path_buf())
+    };
+    Some(LinkTarget::Local { path, anchor })
+}
+
+/// Markdown files of the scanned corpus by lowercase filename stem, for
+/// resolving wikilinks.
+#[derive(Debug, Clone, Default)]
+pub struct WikiIndex {
+    by_stem: HashMap<String, Vec<PathBuf>>,
+}
+
+impl WikiIndex {
+    pub fn new(files: &[PathBuf]) -> Self {
+        let mut by_stem: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for path in files {
+            if let Some(stem) = path.file_stem() {
+                by_stem
+                    .entry(stem.to_string_lossy().to_lowercase())
+                    .or_default()
+                    .push(path.clone());
+            }
+        }
+        Self { by_stem }
+    }
+
+    /// The file a wikilink `page` names. A page may include folders
+    /// (`adr/ADR-001`) to disambiguate; otherwise, when several files share
+    /// the name, the one closest to `from` wins.
+    pub fn resolve(&self, from: &Path, page: &str) -> Option<&Path> {
+        let page = page.to_lowercase();
+        let page = page.strip_suffix(".md").unwrap_or(&page);
+        let stem = page.rsplit('/').next().unwrap_or(page);
+        let suffix = format!("/{}", page);
+
+        self.by_stem
+            .get(stem)?
+            .iter()
+            .filter(|path| {
+                !page.contains('/')
+                    || path
+                        .with_extension("")
+                        .to_string_lossy()
+                        .to_lowercase()
+                        .ends_with(&suffix)
+            })
+            .min_by_key(|path| {
+                let shared = path
+                    .components()
+                    .zip(from.components())
+                    .take_while(|(a, b)| a == b)
+                    .count();
+                (Reverse(shared), path.components().count())
+            })
+            .map(PathBuf::as_path)
+    }
+}
+
+fn has_scheme(dest: &str) -> bool {
+    let Some((scheme, _)) = dest.split_once(':') else {
+        return false;
+    };
+    // A single letter is a Windows drive, not a scheme
+    scheme.len() > 1
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+}
+
+/// Collapse `.` and `..` components without touching the filesystem, so the
+/// result matches the paths stored in the file tree.
+fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(Ok(byte)) = s.get(i + 1..i + 3).map(|hex| u8::from_str_radix(hex, 16)) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// GitHub-style anchor for a heading: lowercase, punctuation dropped,
+/// spaces turned into hyphens.
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Source line of the heading whose anchor is `anchor`. Repeated headings
+/// get `-1`, `-2`, ... suffixes as on GitHub.
+pub fn find_anchor(content: &str, anchor: &str) -> Option<usize> {
+    let wanted = anchor.to_lowercase();
+    let mut seen: Vec<String> = Vec::new();
+    for heading in extract_headings(content) {
+        let base = slugify(&heading.text);
+        let count = seen.iter().filter(|s| **s == base).count();
+        let slug = if count == 0 {
+            base.clone()
+        } else {
+            format!("{}-{}", base, count)
+        };
+        seen.push(base);
+        if slug == wanted {
+            return Some(heading.line);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(path: &str, anchor: Option<&str>) -> LinkTarget {
+        LinkTarget::Local {
+            path: Some(PathBuf::from(path)),
+            anchor: anchor.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn relative_links_resolve_next_to_the_document() {
+        let roots = [PathBuf::from("/home/u/docs")];
+        let from = Path::new("/home/u/docs/guide/intro.md");
+        assert_eq!(
+            resolve_link(from, "../setup.md#install", &roots),
+            local("/home/u/docs/setup.md", Some("install"))
+        );
+    }
+
+    #[test]
+    fn root_relative_links_resolve_against_the_scanned_root() {
+        let roots = [PathBuf::from("/home/u/docs"), PathBuf::from("/home/u/dev")];
+        let from = Path::new("/home/u/docs/guide/intro.md");
+        assert_eq!(
+            resolve_link(from, "/ops/setup.md", &roots),
+            local("/home/u/docs/ops/setup.md", None)
+        );
+    }
+
+    #[test]
+    fn root_relative_links_outside_the_roots_use_the_filesystem_root() {
+        let from = Path::new("/srv/notes/a.md");
+        assert_eq!(
+            resolve_link(from, "/etc/motd.md", &[PathBuf::from("/home/u/docs")]),
+            local("/etc/motd.md", None)
+        );
+    }
+
+    #[test]
+    fn urls_are_external() {
+        assert_eq!(
+            resolve_link(Path::new("/a.md"), "https://example.com", &[]),
+            LinkTarget::External("https://example.com".to_string())
+        );
+    }
+
+    fn wiki() -> WikiIndex {
+        WikiIndex::new(&[
+            PathBuf::from("/notes/index.md"),
+            PathBuf::from("/notes/projects/index.md"),
+            PathBuf::from("/notes/projects/alpha/Setup.md"),
+            PathBuf::from("/notes/ops/setup.md"),
+            PathBuf::from("/notes/ops/deep/runbook.md"),
+        ])
+    }
+
+    #[test]
+    fn wikilinks_prefer_the_closest_file() {
+        let wiki = wiki();
+        let resolve = |from: &str, page: &str| wiki.resolve(Path::new(from), page);
+        assert_eq!(
+            resolve("/notes/projects/alpha/plan.md", "index"),
+            Some(Path::new("/notes/projects/index.md"))
+        );
+        assert_eq!(
+            resolve("/notes/todo.md", "index"),
+            Some(Path::new("/notes/index.md"))
+        );
+        assert_eq!(
+            resolve("/notes/ops/deep/runbook.md", "SETUP"),
+            Some(Path::new("/notes/ops/setup.md"))
+        );
+        assert_eq!(
+            resolve("/notes/projects/todo.md", "setup"),
+            Some(Path::new("/notes/projects/alpha/Setup.md"))
+        );
+        assert_eq!(resolve("/notes/todo.md", "missing"), None);
+    }
+
+    #[test]
+    fn wikilinks_can_name_folders_and_extensions() {
+        let wiki = wiki();
+        let from = Path::new("/notes/projects/alpha/plan.md");
+        assert_eq!(
+            wiki.resolve(from, "ops/setup"),
+            Some(Path::new("/notes/ops/setup.md"))
+        );
+        assert_eq!(
+            wiki.resolve(from, "ops/setup.md"),
+            Some(Path::new("/notes/ops/setup.md"))
+        );
+        assert_eq!(
+            wiki.resolve(from, "alpha/setup.md"),
+            Some(Path::new("/notes/projects/alpha/Setup.md"))
+        );
+        // Folders have to match whole path components
+        assert_eq!(wiki.resolve(from, "ps/setup"), None);
+    }
+
+    #[test]
+    fn wikilinks_keep_the_heading_slug_and_drop_the_alias() {
+        let wiki = wiki();
+        let from = Path::new("/notes/todo.md");
+        let links = crate::markdown::graph::extract_links(
+            "[[ops/setup#First Steps!|the setup]] and [[#Local Notes]]",
+        );
+        let targets: Vec<_> = links
+            .iter()
+            .map(|(link, _)| match link {
+                LinkRef::Wiki(dest) => resolve_wikilink(from, dest, &wiki),
+                LinkRef::Markdown(_) => None,
+            })
+            .collect();
+        assert_eq!(
+            targets,
+            [
+                Some(local("/notes/ops/setup.md", Some("first-steps"))),
+                Some(LinkTarget::Local {
+                    path: None,
+                    anchor: Some("local-notes".to_string()),
+                }),
+            ]
+        );
+        assert_eq!(resolve_wikilink(from, "missing#top", &wiki), None);
+    }
+}
@@ -0,0 +1,367 @@
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+use std::time::SystemTime;
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+
+use crate::markdown::image::{load_image, ImageSettings};
+use crate::markdown::links::{resolve_link, resolve_wikilink, LinkTarget, WikiIndex};
+use crate::markdown::render::{render_markdown, RenderedDocument};
+use crate::markdown::source::render_source;
+
+/// Environment variable overriding the preview size cap, in bytes or with a
+/// `K`/`M` suffix, e.g. `MD_EXPLORER_PREVIEW_LIMIT=4M`.
+const LIMIT_VAR: &str = "MD_EXPLORER_PREVIEW_LIMIT";
+const DEFAULT_LIMIT: u64 = 1024 * 1024;
+
+/// Bytes inspected for NUL characters when detecting binary files.
+const BINARY_SNIFF_LEN: usize = 8192;
+
+/// How a preview is rendered. Renderings made with different options are
+/// cached separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PreviewOptions {
+    /// Show the literal source instead of rendering it
+    pub raw: bool,
+    /// How to draw images, or `None` to show their alt text
+    pub images: Option<ImageSettings>,
+}
+
+struct Request {
+    path: PathBuf,
+    width: u16,
+    options: PreviewOptions,
+    wiki: Arc<WikiIndex>,
+    /// Scanned roots, for root-relative links
+    roots: Vec<PathBuf>,
+}
+
+/// A preview rendered by the loader thread.
+pub struct LoadedPreview {
+    pub path: PathBuf,
+    pub width: u16,
+    pub options: PreviewOptions,
+    /// Modification time read before the file, for cache invalidation
+    pub mtime: Option<SystemTime>,
+    pub document: RenderedDocument,
+}
+
+/// Reads and renders previews on a background thread so large files do not
+/// block input. Only the most recent request is worked on.
+pub struct PreviewLoader {
+    requests: Sender<Request>,
+    results: Receiver<LoadedPreview>,
+    pending: Option<(PathBuf, u16, PreviewOptions)>,
+}
+
+impl PreviewLoader {
+    pub fn new() -> Self {
+        let (request_tx, request_rx) = mpsc::channel::<Request>();
+        let (result_tx, result_rx) = mpsc::channel();
+        let limit = preview_limit();
+
+        thread::spawn(move || {
+            while let Ok(mut request) = request_rx.recv() {
+                // Skip previews the user has already moved past
+                while let Ok(newer) = request_rx.try_recv() {
+                    request = newer;
+                }
+                let mtime = fs::metadata(&request.path).and_then(|m| m.modified()).ok();
+                let document = load_preview(
+                    &request.path,
+                    request.width,
+                    request.options,
+                    &request.wiki,
+                    &request.roots,
+                    limit,
+                );
+                let loaded = LoadedPreview {
+                    path: request.path,
+                    width: request.width,
+                    options: request.options,
+                    mtime,
+                    document,
+                };
+                if result_tx.send(loaded).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            requests: request_tx,
+            results: result_rx,
+            pending: None,
+        }
+    }
+
+    /// Ask for `path` to be rendered at `width` with `options`, unless that
+    /// is already pending.
+    pub fn request(
+        &mut self,
+        path: &Path,
+        width: u16,
+        options: PreviewOptions,
+        wiki: Arc<WikiIndex>,
+        roots: Vec<PathBuf>,
+    ) {
+        if self
+            .pending
+            .as_ref()
+            .is_some_and(|(p, w, o)| p == path && *w == width && *o == options)
+        {
+            return;
+        }
+        self.pending = Some((path.to_path_buf(), width, options));
+        let _ = self.requests.send(Request {
+            path: path.to_path_buf(),
+            width,
+            options,
+            wiki,
+            roots,
+        });
+    }
+
+    /// A finished preview, if any.
+    pub fn try_recv(&mut self) -> Option<LoadedPreview> {
+        let loaded = self.results.try_recv().ok()?;
+        if self.pending.as_ref().is_some_and(|(p, w, o)| {
+            *p == loaded.path && *w == loaded.width && *o == loaded.options
+        }) {
+            self.pending = None;
+        }
+        Some(loaded)
+    }
+}
+
+impl Default for PreviewLoader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn preview_limit() -> u64 {
+    std::env::var(LIMIT_VAR)
+        .ok()
+        .and_then(|value| parse_size(&value))
+        .unwrap_or(DEFAULT_LIMIT)
+}
+
+fn parse_size(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, unit) = match value.char_indices().last()? {
+        (i, 'k' | 'K') => (&value[..i], 1024),
+        (i, 'm' | 'M') => (&value[..i], 1024 * 1024),
+        _ => (value, 1),
+    };
+    number.trim().parse::<u64>().ok()?.checked_mul(unit)
+}
+
+/// Read up to `limit` bytes of `path` and render them as `options` asks.
+/// Binary and non-UTF-8 files get a notice instead, and a cut-off file ends
+/// with a truncation notice.
+pub fn load_preview(
+    path: &Path,
+    width: u16,
+    options: PreviewOptions,
+    wiki: &WikiIndex,
+    roots: &[PathBuf],
+    limit: u64,
+) -> RenderedDocument {
+    let notice = |text: String| {
+        RenderedDocument::placeholder(
+            vec![
+                Line::from(""),
+                Line::from(Span::styled(
+                    format!("  {}", text),
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ],
+            width,
+        )
+    };
+
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    let mut bytes = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(limit).read_to_end(&mut bytes));
+    if let Err(e) = read {
+        return notice(format!("Error reading file: {}", e));
+    }
+
+    if bytes[..bytes.len().min(BINARY_SNIFF_LEN)].contains(&0) {
+        return notice(format!("Binary file ({}), not shown", format_size(size)));
+    }
+
+    let truncated = size > bytes.len() as u64;
+    let text = match std::str::from_utf8(&bytes) {
+        Ok(text) => text,
+        // The cut may split a multi-byte character; keep what came before it
+        Err(e) if truncated && e.error_len().is_none() => {
+            std::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(e) => {
+            return notice(format!(
+                "Not valid UTF-8 (invalid byte at offset {}), not shown",
+                e.valid_up_to()
+            ))
+        }
+    };
+    // Don't end on half a line
+    let text = if truncated {
+        text.rfind('\n').map_or(text, |end| &text[..=end])
+    } else {
+        text
+    };
+
+    let resolves = |dest: &str| resolve_wikilink(path, dest, wiki).is_some();
+    let images = |dest: &str, max_cols: u16| {
+        let settings = options.images?;
+        match resolve_link(path, dest, roots) {
+            LinkTarget::Local {
+                path: Some(image), ..
+            } => load_image(&image, max_cols, settings).ok(),
+            _ => None,
+        }
+    };
+    let mut document = if options.raw {
+        render_source(text, width)
+    } else {
+        render_markdown(text, width, &resolves, &images)
+    };
+
+    if truncated {
+        let last_line = text.lines().count();
+        let style = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        document.push_line(Line::from(""), last_line);
+        document.push_line(
+            Line::from(Span::styled(
+                format!(
+                    "── Preview truncated: showing {} of {} ──",
+                    format_size(text.len() as u64),
+                    format_size(size)
+                ),
+                style,
+            )),
+            last_line,
+        );
+        document.push_line(
+            Line::from(Span::styled(
+                format!(
+                    "Press Enter in the tree to open the full file, or raise {}",
+                    LIMIT_VAR
+                ),
+                Style::default().fg(Color::DarkGray),
+            )),
+            last_line,
+        );
+    }
+    document
+}
+
+fn format_size(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    let bytes_f = bytes as f64;
+    if bytes_f >= KIB * KIB {
+        format!("{:.1} MiB", bytes_f / (KIB * KIB))
+    } else if bytes_f >= KIB {
+        format!("{:.1} KiB", bytes_f / KIB)
+    } else {
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OPTIONS: PreviewOptions = PreviewOptions {
+        raw: false,
+        images: None,
+    };
+
+    /// Write `bytes` to a temp file and return the preview's lines as text.
+    fn preview(name: &str, bytes: &[u8], limit: u64) -> Vec<String> {
+        let path = std::env::temp_dir().join(format!(
+            "md-explorer-loader-{}-{}.md",
+            std::process::id(),
+            name
+        ));
+        fs::write(&path, bytes).unwrap();
+        let document = load_preview(&path, 80, OPTIONS, &WikiIndex::default(), &[], limit);
+        fs::remove_file(&path).unwrap();
+        document
+            .lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn parses_sizes() {
+        assert_eq!(parse_size("2048"), Some(2048));
+        assert_eq!(parse_size(" 4k "), Some(4096));
+        assert_eq!(parse_size("3 M"), Some(3 * 1024 * 1024));
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("M"), None);
+        assert_eq!(parse_size("4G"), None);
+        assert_eq!(parse_size("-1"), None);
+        assert_eq!(parse_size("lots"), None);
+        assert_eq!(parse_size("99999999999999999999M"), None);
+        assert_eq!(parse_size(&format!("{}M", u64::MAX / 1024)), None);
+    }
+
+    #[test]
+    fn binary_files_get_a_notice() {
+        let lines = preview("binary", b"PK\x03\x04\0\0 data", 1024);
+        assert_eq!(lines, ["", "  Binary file (11 B), not shown"]);
+    }
+
+    #[test]
+    fn invalid_utf8_gets_a_notice() {
+        let lines = preview("latin1", b"caf\xe9 au lait\n", 1024);
+        assert_eq!(
+            lines,
+            [
+                "",
+                "  Not valid UTF-8 (invalid byte at offset 3), not shown"
+            ]
+        );
+    }
+
+    #[test]
+    fn truncates_at_a_line_boundary() {
+        let lines = preview("cut", b"first line\nsecond line\nthird", 15);
+        assert_eq!(lines[0], "first line");
+        assert!(!lines.iter().any(|line| line.contains("second")));
+        assert!(lines.contains(&"── Preview truncated: showing 11 B of 28 B ──".to_string()));
+        assert!(lines.last().unwrap().contains(LIMIT_VAR));
+    }
+
+    #[test]
+    fn truncates_before_a_split_character() {
+        // The limit falls inside the two-byte `é` and there is no newline
+        let lines = preview("split", "naïve café".as_bytes(), 10);
+        assert_eq!(lines[0], "naïve caf");
+        assert!(lines
+            .iter()
+            .any(|line| line.contains("showing 10 B of 12 B")));
+    }
+
+    #[test]
+    fn whole_files_have_no_notice() {
+        let lines = preview("whole", "# Title\n\nbody\n".as_bytes(), 1024);
+        assert!(!lines.iter().any(|line| line.contains("truncated")));
+    }
+}
@@ -0,0 +1,581 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// Convert LaTeX math to a unicode approximation: Greek letters, operators,
+/// sub/superscripts, fractions, roots and accents. `\\` line breaks become
+/// `\n`. Returns `None` for commands or environments it doesn't know, so the
+/// caller can show the TeX source instead.
+pub fn latex_to_unicode(tex: &str) -> Option<String> {
+    let mut chars = tex.chars().peekable();
+    let out = parse_group(&mut chars, false)?;
+    if chars.next().is_some() {
+        return None;
+    }
+    let lines: Vec<String> = out.split('\n').map(collapse_spaces).collect();
+    Some(lines.join("\n"))
+}
+
+/// Parse atoms until the end of input, or the closing `}` when `braced`.
+fn parse_group(chars: &mut Peekable<Chars>, braced: bool) -> Option<String> {
+    let mut out = String::new();
+    loop {
+        match chars.peek() {
+            None if braced => return None,
+            None => return Some(out),
+            Some('}') if braced => {
+                chars.next();
+                return Some(out);
+            }
+            Some('}') => return None,
+            _ => {}
+        }
+        match chars.next()? {
+            '^' => {
+                let arg = parse_argument(chars)?;
+                out.push_str(&script(&arg, superscript_char, '^'));
+            }
+            '_' => {
+                let arg = parse_argument(chars)?;
+                out.push_str(&script(&arg, subscript_char, '_'));
+            }
+            '{' => out.push_str(&parse_group(chars, true)?),
+            '\\' => out.push_str(&parse_command(chars)?),
+            '\'' => out.push('′'),
+            '~' => out.push(' '),
+            '-' => out.push('−'),
+            // Alignment points only mean something in environments
+            '&' => return None,
+            c if c.is_whitespace() => out.push(' '),
+            c => out.push(c),
+        }
+    }
+}
+
+/// A single argument: a `{group}`, a command, or one character.
+fn parse_argument(chars: &mut Peekable<Chars>) -> Option<String> {
+    skip_spaces(chars);
+    match chars.next()? {
+        '{' => parse_group(chars, true),
+        '\\' => parse_command(chars),
+        '}' | '^' | '_' | '&' => None,
+        c => Some(c.to_string()),
+    }
+}
+
+/// An optional `[argument]`, as in `\sqrt[3]{x}`.
+fn parse_optional(chars: &mut Peekable<Chars>) -> Option<Option<String>> {
+    skip_spaces(chars);
+    if chars.peek() != Some(&'[') {
+        return Some(None);
+    }
+    chars.next();
+    let mut out = String::new();
+    loop {
+        match chars.peek()? {
+            ']' => {
+                chars.next();
+                return Some(Some(out));
+            }
+            '\\' => {
+                chars.next();
+                out.push_str(&parse_command(chars)?);
+            }
+            _ => out.push(chars.next()?),
+        }
+    }
+}
+
+fn skip_spaces(chars: &mut Peekable<Chars>) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+/// Raw text of a `{...}` argument, for `\text` and friends.
+fn parse_text(chars: &mut Peekable<Chars>) -> Option<String> {
+    skip_spaces(chars);
+    if chars.next()? != '{' {
+        return None;
+    }
+    let mut out = String::new();
+    let mut depth = 0;
+    loop {
+        match chars.next()? {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(out),
+            '}' => depth -= 1,
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    out.push(c);
+                }
+            }
+            c => out.push(c),
+        }
+    }
+}
+
+/// Everything after a backslash.
+fn parse_command(chars: &mut Peekable<Chars>) -> Option<String> {
+    let first = chars.next()?;
+    if !first.is_ascii_alphabetic() {
+        return match first {
+            '\\' => Some("\n".to_string()),
+            ',' | ':' | ';' | ' ' => Some(" ".to_string()),
+            '!' => Some(String::new()),
+            '{' | '}' | '$' | '%' | '#' | '&' | '_' => Some(first.to_string()),
+            '|' => Some("‖".to_string()),
+            _ => None,
+        };
+    }
+
+    let mut name = first.to_string();
+    while let Some(&c) = chars.peek() {
+        if !c.is_ascii_alphabetic() {
+            break;
+        }
+        name.push(c);
+        chars.next();
+    }
+    // Spaces after a control word only end it
+    skip_spaces(chars);
+
+    if let Some(symbol) = symbol(&name) {
+        return Some(symbol.to_string());
+    }
+    if let Some(relation) = relation(&name) {
+        return Some(format!(" {} ", relation));
+    }
+    if FUNCTIONS.contains(&name.as_str()) {
+        // Keep `\sin x` apart, but not `\sin(x)`
+        let spaced = chars
+            .peek()
+            .is_some_and(|c| c.is_alphanumeric() || matches!(c, '\\' | '{'));
+        return Some(if spaced { format!("{} ", name) } else { name });
+    }
+
+    match name.as_str() {
+        "frac" | "dfrac" | "tfrac" => {
+            let numerator = parse_argument(chars)?;
+            let denominator = parse_argument(chars)?;
+            Some(fraction(&numerator, &denominator))
+        }
+        "sqrt" => {
+            let index = parse_optional(chars)?;
+            let radicand = parse_argument(chars)?;
+            let root = match index.as_deref().map(str::trim) {
+                None | Some("2") => "√".to_string(),
+                Some("3") => "∛".to_string(),
+                Some("4") => "∜".to_string(),
+                Some(index) => format!("{}√", script(index, superscript_char, '^')),
+            };
+            Some(format!("{}{}", root, grouped(&radicand)))
+        }
+        "text" | "textrm" | "textit" | "textbf" | "mathrm" | "mathit" | "mathbf" | "mathsf"
+        | "boldsymbol" | "operatorname" | "mbox" => parse_text(chars),
+        "mathbb" => Some(parse_text(chars)?.chars().map(double_struck).collect()),
+        "hat" | "widehat" => accent(chars, '\u{302}'),
+        "bar" | "overline" => accent(chars, '\u{305}'),
+        "tilde" | "widetilde" => accent(chars, '\u{303}'),
+        "dot" => accent(chars, '\u{307}'),
+        "ddot" => accent(chars, '\u{308}'),
+        "vec" => accent(chars, '\u{20d7}'),
+        "quad" => Some("  ".to_string()),
+        "qquad" => Some("    ".to_string()),
+        // Sizing commands leave the delimiter that follows them
+        "left" | "right" | "big" | "Big" | "bigg" | "Bigg" | "bigl" | "bigr" | "Bigl" | "Bigr"
+        | "displaystyle" | "textstyle" | "limits" | "nolimits" => {
+            if chars.peek() == Some(&'.') {
+                chars.next();
+            }
+            Some(String::new())
+        }
+        _ => None,
+    }
+}
+
+/// Combine `mark` with every character of the argument.
+fn accent(chars: &mut Peekable<Chars>, mark: char) -> Option<String> {
+    let arg = parse_argument(chars)?;
+    Some(arg.chars().flat_map(|c| [c, mark]).collect())
+}
+
+fn fraction(numerator: &str, denominator: &str) -> String {
+    let (numerator, denominator) = (numerator.trim(), denominator.trim());
+    let digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+    if digits(numerator) && digits(denominator) {
+        return format!(
+            "{}⁄{}",
+            script(numerator, superscript_char, '^'),
+            script(denominator, subscript_char, '_')
+        );
+    }
+    format!("{}/{}", grouped(numerator), grouped(denominator))
+}
+
+/// `text` as a single term, parenthesised if it has spaces or operators.
+fn grouped(text: &str) -> String {
+    let text = text.trim();
+    let operator = |c: char| c.is_whitespace() || "+−-=<>/±∓×÷⋅,".contains(c);
+    if !text.chars().any(operator) {
+        text.to_string()
+    } else {
+        format!("({})", text)
+    }
+}
+
+/// Raise or lower `text` with unicode script characters, or fall back to
+/// `^(text)` when some character has no script form.
+fn script(text: &str, convert: fn(char) -> Option<char>, marker: char) -> String {
+    let text = text.trim();
+    let converted: Option<String> = text
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(convert)
+        .collect();
+    match converted {
+        Some(converted) if !converted.is_empty() => converted,
+        _ if text.chars().count() == 1 => format!("{}{}", marker, text),
+        _ => format!("{}({})", marker, text),
+    }
+}
+
+fn collapse_spaces(line: &str) -> String {
+    let mut out = String::new();
+    for word in line.split(' ').filter(|w| !w.is_empty()) {
+        if !out.is_empty() {
+            out.push(' ');
+        }
+        out.push_str(word);
+    }
+    out
+}
+
+const FUNCTIONS: &[&str] = &[
+    "sin", "cos", "tan", "cot", "sec", "csc", "arcsin", "arccos", "arctan", "sinh", "cosh", "tanh",
+    "log", "ln", "lg", "exp", "lim", "max", "min", "sup", "inf", "det", "gcd", "deg", "dim", "ker",
+    "arg", "mod", "Pr",
+];
+
+/// Letters, large operators and other symbols that sit next to their
+/// neighbours.
+fn symbol(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "alpha" => "α",
+        "beta" => "β",
+        "gamma" => "γ",
+        "delta" => "δ",
+        "epsilon" => "ϵ",
+        "varepsilon" => "ε",
+        "zeta" => "ζ",
+        "eta" => "η",
+        "theta" => "θ",
+        "vartheta" => "ϑ",
+        "iota" => "ι",
+        "kappa" => "κ",
+        "lambda" => "λ",
+        "mu" => "μ",
+        "nu" => "ν",
+        "xi" => "ξ",
+        "pi" => "π",
+        "varpi" => "ϖ",
+        "rho" => "ρ",
+        "varrho" => "ϱ",
+        "sigma" => "σ",
+        "varsigma" => "ς",
+        "tau" => "τ",
+        "upsilon" => "υ",
+        "phi" => "ϕ",
+        "varphi" => "φ",
+        "chi" => "χ",
+        "psi" => "ψ",
+        "omega" => "ω",
+        "Gamma" => "Γ",
+        "Delta" => "Δ",
+        "Theta" => "Θ",
+        "Lambda" => "Λ",
+        "Xi" => "Ξ",
+        "Pi" => "Π",
+        "Sigma" => "Σ",
+        "Upsilon" => "Υ",
+        "Phi" => "Φ",
+        "Psi" => "Ψ",
+        "Omega" => "Ω",
+        "sum" => "∑",
+        "prod" => "∏",
+        "coprod" => "∐",
+        "int" => "∫",
+        "iint" => "∬",
+        "iiint" => "∭",
+        "oint" => "∮",
+        "bigcup" => "⋃",
+        "bigcap" => "⋂",
+        "infty" => "∞",
+        "partial" => "∂",
+        "nabla" => "∇",
+        "forall" => "∀",
+        "exists" => "∃",
+        "nexists" => "∄",
+        "neg" | "lnot" => "¬",
+        "emptyset" | "varnothing" => "∅",
+        "ldots" | "dots" => "…",
+        "cdots" => "⋯",
+        "vdots" => "⋮",
+        "ddots" => "⋱",
+        "prime" => "′",
+        "angle" => "∠",
+        "triangle" => "△",
+        "hbar" => "ℏ",
+        "ell" => "ℓ",
+        "Re" => "ℜ",
+        "Im" => "ℑ",
+        "aleph" => "ℵ",
+        "degree" => "°",
+        "langle" => "⟨",
+        "rangle" => "⟩",
+        "lceil" => "⌈",
+        "rceil" => "⌉",
+        "lfloor" => "⌊",
+        "rfloor" => "⌋",
+        "lbrace" => "{",
+        "rbrace" => "}",
+        "vert" => "|",
+        "Vert" => "‖",
+        "top" => "⊤",
+        "bot" => "⊥",
+        _ => return None,
+    })
+}
+
+/// Binary operators, relations and arrows, spaced from their operands.
+fn relation(name: &str) -> Option<&'static str> {
+    Some(match name {
+        "pm" => "±",
+        "mp" => "∓",
+        "times" => "×",
+        "div" => "÷",
+        "cdot" => "⋅",
+        "circ" => "∘",
+        "ast" => "∗",
+        "star" => "⋆",
+        "bullet" => "∙",
+        "oplus" => "⊕",
+        "ominus" => "⊖",
+        "otimes" => "⊗",
+        "cup" => "∪",
+        "cap" => "∩",
+        "setminus" => "∖",
+        "land" | "wedge" => "∧",
+        "lor" | "vee" => "∨",
+        "leq" | "le" => "≤",
+        "geq" | "ge" => "≥",
+        "neq" | "ne" => "≠",
+        "approx" => "≈",
+        "equiv" => "≡",
+        "sim" => "∼",
+        "simeq" => "≃",
+        "cong" => "≅",
+        "propto" => "∝",
+        "ll" => "≪",
+        "gg" => "≫",
+        "in" => "∈",
+        "notin" => "∉",
+        "ni" => "∋",
+        "subset" => "⊂",
+        "subseteq" => "⊆",
+        "supset" => "⊃",
+        "supseteq" => "⊇",
+        "mid" => "∣",
+        "parallel" => "∥",
+        "perp" => "⊥",
+        "to" | "rightarrow" => "→",
+        "leftarrow" | "gets" => "←",
+        "leftrightarrow" => "↔",
+        "Rightarrow" => "⇒",
+        "Leftarrow" => "⇐",
+        "Leftrightarrow" | "iff" => "⇔",
+        "implies" => "⟹",
+        "impliedby" => "⟸",
+        "mapsto" => "↦",
+        "uparrow" => "↑",
+        "downarrow" => "↓",
+        _ => return None,
+    })
+}
+
+fn superscript_char(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '⁰',
+        '1' => '¹',
+        '2' => '²',
+        '3' => '³',
+        '4' => '⁴',
+        '5' => '⁵',
+        '6' => '⁶',
+        '7' => '⁷',
+        '8' => '⁸',
+        '9' => '⁹',
+        '+' => '⁺',
+        '-' | '−' => '⁻',
+        '=' => '⁼',
+        '(' => '⁽',
+        ')' => '⁾',
+        'a' => 'ᵃ',
+        'b' => 'ᵇ',
+        'c' => 'ᶜ',
+        'd' => 'ᵈ',
+        'e' => 'ᵉ',
+        'f' => 'ᶠ',
+        'g' => 'ᵍ',
+        'h' => 'ʰ',
+        'i' => 'ⁱ',
+        'j' => 'ʲ',
+        'k' => 'ᵏ',
+        'l' => 'ˡ',
+        'm' => 'ᵐ',
+        'n' => 'ⁿ',
+        'o' => 'ᵒ',
+        'p' => 'ᵖ',
+        'r' => 'ʳ',
+        's' => 'ˢ',
+        't' => 'ᵗ',
+        'u' => 'ᵘ',
+        'v' => 'ᵛ',
+        'w' => 'ʷ',
+        'x' => 'ˣ',
+        'y' => 'ʸ',
+        'z' => 'ᶻ',
+        'A' => 'ᴬ',
+        'B' => 'ᴮ',
+        'D' => 'ᴰ',
+        'E' => 'ᴱ',
+        'G' => 'ᴳ',
+        'H' => 'ᴴ',
+        'I' => 'ᴵ',
+        'J' => 'ᴶ',
+        'K' => 'ᴷ',
+        'L' => 'ᴸ',
+        'M' => 'ᴹ',
+        'N' => 'ᴺ',
+        'O' => 'ᴼ',
+        'P' => 'ᴾ',
+        'R' => 'ᴿ',
+        'T' => 'ᵀ',
+        'U' => 'ᵁ',
+        'V' => 'ⱽ',
+        'W' => 'ᵂ',
+        'α' => 'ᵅ',
+        'β' => 'ᵝ',
+        'γ' => 'ᵞ',
+        'δ' => 'ᵟ',
+        'ε' | 'ϵ' => 'ᵋ',
+        'θ' => 'ᶿ',
+        'ι' => 'ᶥ',
+        'φ' | 'ϕ' => 'ᵠ',
+        'χ' => 'ᵡ',
+        '′' => '′',
+        _ => return None,
+    })
+}
+
+fn subscript_char(c: char) -> Option<char> {
+    Some(match c {
+        '0' => '₀',
+        '1' => '₁',
+        '2' => '₂',
+        '3' => '₃',
+        '4' => '₄',
+        '5' => '₅',
+        '6' => '₆',
+        '7' => '₇',
+        '8' => '₈',
+        '9' => '₉',
+        '+' => '₊',
+        '-' | '−' => '₋',
+        '=' => '₌',
+        '(' => '₍',
+        ')' => '₎',
+        'a' => 'ₐ',
+        'e' => 'ₑ',
+        'h' => 'ₕ',
+        'i' => 'ᵢ',
+        'j' => 'ⱼ',
+        'k' => 'ₖ',
+        'l' => 'ₗ',
+        'm' => 'ₘ',
+        'n' => 'ₙ',
+        'o' => 'ₒ',
+        'p' => 'ₚ',
+        'r' => 'ᵣ',
+        's' => 'ₛ',
+        't' => 'ₜ',
+        'u' => 'ᵤ',
+        'v' => 'ᵥ',
+        'x' => 'ₓ',
+        'β' => 'ᵦ',
+        'γ' => 'ᵧ',
+        'ρ' => 'ᵨ',
+        'φ' | 'ϕ' => 'ᵩ',
+        'χ' => 'ᵪ',
+        _ => return None,
+    })
+}
+
+/// `\mathbb` letters; the common number sets have their own code points.
+fn double_struck(c: char) -> char {
+    match c {
+        'C' => 'ℂ',
+        'H' => 'ℍ',
+        'N' => 'ℕ',
+        'P' => 'ℙ',
+        'Q' => 'ℚ',
+        'R' => 'ℝ',
+        'Z' => 'ℤ',
+        'A'..='Z' => char::from_u32(0x1D538 + (c as u32 - 'A' as u32)).unwrap_or(c),
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_to_unicode() {
+        let cases = [
+            (r"\frac{1}{2}", "¹⁄₂"),
+            (r"\frac{x}{y}", "x/y"),
+            (r"\frac{a+b}{c}", "(a+b)/c"),
+            (r"\sqrt{x}", "√x"),
+            (r"\sqrt[3]{x}", "∛x"),
+            (r"\sqrt[n]{x+1}", "ⁿ√(x+1)"),
+            (r"x^2 + y_i", "x² + yᵢ"),
+            (r"x^{n+1}", "xⁿ⁺¹"),
+            (r"a_{ij}", "aᵢⱼ"),
+            (r"e^{i\pi}", "e^(iπ)"),
+            (r"x^\pi", "x^π"),
+            (r"\alpha \leq \beta", "α ≤ β"),
+            (r"\sin x", "sin x"),
+            (r"\text{if } x", "if x"),
+            (r"a \\ b", "a\nb"),
+        ];
+        for (tex, expected) in cases {
+            assert_eq!(latex_to_unicode(tex).as_deref(), Some(expected), "{}", tex);
+        }
+    }
+
+    #[test]
+    fn unknown_syntax_is_none() {
+        let cases = [
+            r"\foo",
+            r"\begin{matrix} a \end{matrix}",
+            r"a & b",
+            r"\frac{1}",
+            r"{x",
+            r"x}",
+            r"\sqrt[3{x}",
+        ];
+        for tex in cases {
+            assert_eq!(latex_to_unicode(tex), None, "{}", tex);
+        }
+    }
+}
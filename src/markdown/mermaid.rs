@@ -0,0 +1,1423 @@
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Draw a mermaid flowchart (`graph`/`flowchart` TD, TB or LR) or
+/// `sequenceDiagram` with box-drawing characters. Returns `None` for other
+/// diagram types, syntax this doesn't understand, or a diagram wider than
+/// `width`, so the caller can show the source instead.
+pub fn render_mermaid(source: &str, width: usize) -> Option<Vec<Line<'static>>> {
+    let mut lines = source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("%%"));
+    let header = lines.next()?;
+    let body: Vec<&str> = lines.collect();
+
+    let mut words = header.split_whitespace();
+    let canvas = match words.next()? {
+        "graph" | "flowchart" => {
+            let direction = match words.next().unwrap_or("TD") {
+                "TD" | "TB" => Direction::Down,
+                "LR" => Direction::Right,
+                _ => return None,
+            };
+            draw_flowchart(&parse_flowchart(&body)?, direction)
+        }
+        "sequenceDiagram" => draw_sequence(&parse_sequence(&body)?),
+        _ => return None,
+    };
+
+    (canvas.width() <= width).then(|| canvas.into_lines())
+}
+
+// Canvas
+
+const UP: u8 = 1;
+const DOWN: u8 = 2;
+const LEFT: u8 = 4;
+const RIGHT: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stroke {
+    Solid,
+    Dotted,
+    Thick,
+}
+
+#[derive(Clone, Copy)]
+enum Cell {
+    Empty,
+    /// Connecting line; `mask` holds the directions it leaves the cell in
+    Line {
+        mask: u8,
+        stroke: Stroke,
+    },
+    Char(char, Style),
+    /// Second column of a wide character
+    Continuation,
+}
+
+/// A grid of cells that grows as it is drawn on. Lines drawn across each
+/// other join into the matching box-drawing corner or junction.
+#[derive(Default)]
+struct Canvas {
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Canvas {
+    fn width(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    fn cell(&mut self, x: usize, y: usize) -> &mut Cell {
+        if self.rows.len() <= y {
+            self.rows.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.rows[y];
+        if row.len() <= x {
+            row.resize(x + 1, Cell::Empty);
+        }
+        &mut row[x]
+    }
+
+    fn put(&mut self, x: usize, y: usize, c: char, style: Style) {
+        *self.cell(x, y) = Cell::Char(c, style);
+    }
+
+    fn text(&mut self, x: usize, y: usize, text: &str, style: Style) {
+        let mut x = x;
+        for c in text.chars() {
+            let w = c.width().unwrap_or(0);
+            if w == 0 {
+                continue;
+            }
+            self.put(x, y, c, style);
+            if w == 2 {
+                *self.cell(x + 1, y) = Cell::Continuation;
+            }
+            x += w;
+        }
+    }
+
+    /// Add direction `dir` to the line in cell `(x, y)`.
+    fn connect(&mut self, x: usize, y: usize, dir: u8, stroke: Stroke) {
+        let cell = self.cell(x, y);
+        *cell = match *cell {
+            Cell::Line { mask, .. } => Cell::Line {
+                mask: mask | dir,
+                stroke,
+            },
+            Cell::Empty => Cell::Line { mask: dir, stroke },
+            other => other,
+        };
+    }
+
+    /// A straight line between two points in the same row or column.
+    fn line(&mut self, from: (usize, usize), to: (usize, usize), stroke: Stroke) {
+        let (mut x, mut y) = from;
+        if from == to {
+            self.connect(x, y, 0, stroke);
+        }
+        while (x, y) != to {
+            let (nx, ny, out, back) = if to.0 > x {
+                (x + 1, y, RIGHT, LEFT)
+            } else if to.0 < x {
+                (x - 1, y, LEFT, RIGHT)
+            } else if to.1 > y {
+                (x, y + 1, DOWN, UP)
+            } else {
+                (x, y - 1, UP, DOWN)
+            };
+            self.connect(x, y, out, stroke);
+            self.connect(nx, ny, back, stroke);
+            (x, y) = (nx, ny);
+        }
+    }
+
+    fn polyline(&mut self, points: &[(usize, usize)], stroke: Stroke) {
+        for pair in points.windows(2) {
+            self.line(pair[0], pair[1], stroke);
+        }
+    }
+
+    fn rect(&mut self, x: usize, y: usize, w: usize, h: usize, corners: [char; 4], style: Style) {
+        let [tl, tr, bl, br] = corners;
+        for i in 1..w - 1 {
+            self.put(x + i, y, '─', style);
+            self.put(x + i, y + h - 1, '─', style);
+        }
+        for j in 1..h - 1 {
+            self.put(x, y + j, '│', style);
+            self.put(x + w - 1, y + j, '│', style);
+            for i in 1..w - 1 {
+                self.put(x + i, y + j, ' ', Style::default());
+            }
+        }
+        self.put(x, y, tl, style);
+        self.put(x + w - 1, y, tr, style);
+        self.put(x, y + h - 1, bl, style);
+        self.put(x + w - 1, y + h - 1, br, style);
+    }
+
+    fn into_lines(self) -> Vec<Line<'static>> {
+        let line_style = Style::default().fg(Color::Blue);
+        self.rows
+            .into_iter()
+            .map(|row| {
+                let mut spans: Vec<Span<'static>> = Vec::new();
+                let mut text = String::new();
+                let mut style = Style::default();
+                for cell in row {
+                    let (c, cell_style) = match cell {
+                        Cell::Empty => (' ', Style::default()),
+                        Cell::Line { mask, stroke } => (line_char(mask, stroke), line_style),
+                        Cell::Char(c, style) => (c, style),
+                        Cell::Continuation => continue,
+                    };
+                    if cell_style != style && !text.is_empty() {
+                        spans.push(Span::styled(std::mem::take(&mut text), style));
+                    }
+                    style = cell_style;
+                    text.push(c);
+                }
+                let text = text.trim_end().to_string();
+                if !text.is_empty() {
+                    spans.push(Span::styled(text, style));
+                }
+                Line::from(spans)
+            })
+            .collect()
+    }
+}
+
+fn line_char(mask: u8, stroke: Stroke) -> char {
+    let vertical = mask & !(UP | DOWN) == 0;
+    let horizontal = mask & !(LEFT | RIGHT) == 0;
+    match (stroke, vertical, horizontal) {
+        (Stroke::Dotted, true, false) => return '┆',
+        (Stroke::Dotted, false, true) => return '┄',
+        (Stroke::Thick, true, false) => return '┃',
+        (Stroke::Thick, false, true) => return '━',
+        _ => {}
+    }
+    match mask {
+        0 | UP | DOWN => '│',
+        m if m == UP | DOWN => '│',
+        LEFT | RIGHT => '─',
+        m if m == LEFT | RIGHT => '─',
+        m if m == DOWN | RIGHT => '╭',
+        m if m == DOWN | LEFT => '╮',
+        m if m == UP | RIGHT => '╰',
+        m if m == UP | LEFT => '╯',
+        m if m == UP | DOWN | RIGHT => '├',
+        m if m == UP | DOWN | LEFT => '┤',
+        m if m == LEFT | RIGHT | DOWN => '┬',
+        m if m == LEFT | RIGHT | UP => '┴',
+        _ => '┼',
+    }
+}
+
+fn border_style() -> Style {
+    Style::default().fg(Color::Cyan)
+}
+
+fn label_style() -> Style {
+    Style::default().fg(Color::Yellow)
+}
+
+// Flowcharts
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Direction {
+    Down,
+    Right,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Shape {
+    Rect,
+    Round,
+    Decision,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Head {
+    None,
+    Arrow,
+    Circle,
+    Cross,
+}
+
+struct Node {
+    id: String,
+    label: String,
+    shape: Shape,
+}
+
+struct Edge {
+    from: usize,
+    to: usize,
+    stroke: Stroke,
+    head: Head,
+    label: Option<String>,
+}
+
+/// Text and shape of a node written with brackets, such as `A{Text}`.
+type Label = (String, Shape);
+
+#[derive(Default)]
+struct Flowchart {
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+impl Flowchart {
+    /// Index of node `id`, adding it if new. A label given later replaces
+    /// the default one.
+    fn node(&mut self, id: &str, label: Option<Label>) -> usize {
+        let index = match self.nodes.iter().position(|n| n.id == id) {
+            Some(index) => index,
+            None => {
+                self.nodes.push(Node {
+                    id: id.to_string(),
+                    label: id.to_string(),
+                    shape: Shape::Rect,
+                });
+                self.nodes.len() - 1
+            }
+        };
+        if let Some((label, shape)) = label {
+            self.nodes[index].label = label;
+            self.nodes[index].shape = shape;
+        }
+        index
+    }
+}
+
+/// Statements that only affect styling and are skipped.
+const IGNORED_STATEMENTS: [&str; 6] = [
+    "classDef ",
+    "class ",
+    "style ",
+    "linkStyle ",
+    "click ",
+    "direction ",
+];
+
+fn parse_flowchart(body: &[&str]) -> Option<Flowchart> {
+    let mut chart = Flowchart::default();
+    for statement in body.iter().flat_map(|line| line.split(';')) {
+        let statement = statement.trim();
+        if statement.is_empty() || IGNORED_STATEMENTS.iter().any(|s| statement.starts_with(s)) {
+            continue;
+        }
+        parse_chain(statement, &mut chart)?;
+    }
+    (!chart.nodes.is_empty()).then_some(chart)
+}
+
+/// A statement like `A[Start] --> B & C -->|yes| D`.
+fn parse_chain(statement: &str, chart: &mut Flowchart) -> Option<()> {
+    let mut rest = statement;
+    let mut previous: Vec<usize> = Vec::new();
+    let mut pending: Option<(Stroke, Head, Option<String>)> = None;
+    loop {
+        let mut group = Vec::new();
+        loop {
+            let (id, label, after) = parse_node(rest)?;
+            group.push(chart.node(id, label));
+            rest = after.trim_start();
+            match rest.strip_prefix('&') {
+                Some(after) => rest = after.trim_start(),
+                None => break,
+            }
+        }
+        if let Some((stroke, head, label)) = pending.take() {
+            for &from in &previous {
+                for &to in &group {
+                    chart.edges.push(Edge {
+                        from,
+                        to,
+                        stroke,
+                        head,
+                        label: label.clone(),
+                    });
+                }
+            }
+        }
+        if rest.is_empty() {
+            return Some(());
+        }
+        let (stroke, head, label, after) = parse_edge(rest)?;
+        pending = Some((stroke, head, label));
+        previous = group;
+        rest = after.trim_start();
+    }
+}
+
+/// Node shapes by opening bracket, longest first.
+const SHAPES: [(&str, &str, Shape); 12] = [
+    ("(((", ")))", Shape::Round),
+    ("((", "))", Shape::Round),
+    ("([", "])", Shape::Round),
+    ("[[", "]]", Shape::Rect),
+    ("[(", ")]", Shape::Round),
+    ("{{", "}}", Shape::Decision),
+    ("[/", "/]", Shape::Rect),
+    ("[\\", "\\]", Shape::Rect),
+    ("(", ")", Shape::Round),
+    ("[", "]", Shape::Rect),
+    ("{", "}", Shape::Decision),
+    (">", "]", Shape::Rect),
+];
+
+/// A node id with an optional shaped label, and the text after it.
+fn parse_node(text: &str) -> Option<(&str, Option<Label>, &str)> {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .unwrap_or(text.len());
+    if end == 0 {
+        return None;
+    }
+    let (id, mut rest) = text.split_at(end);
+
+    let mut label = None;
+    if let Some(&(open, close, shape)) = SHAPES.iter().find(|(open, _, _)| rest.starts_with(open)) {
+        let inner = &rest[open.len()..];
+        let (raw, after) = match inner.strip_prefix('"') {
+            Some(quoted) => {
+                let end = quoted.find('"')?;
+                (&quoted[..end], quoted[end + 1..].strip_prefix(close)?)
+            }
+            None => {
+                let end = inner.find(close)?;
+                (&inner[..end], &inner[end + close.len()..])
+            }
+        };
+        let text = raw
+            .replace("<br>", " ")
+            .replace("<br/>", " ")
+            .replace('`', "");
+        label = Some((text.trim().to_string(), shape));
+        rest = after;
+    }
+    // Class shorthand such as `A:::warning`
+    if let Some(class) = rest.strip_prefix(":::") {
+        let end = class
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+            .unwrap_or(class.len());
+        rest = &class[end..];
+    }
+    Some((id, label, rest))
+}
+
+/// A link such as `-->`, `-.->`, `==>`, `---`, `--o`, `-->|label|` or
+/// `-- label -->`, and the text after it.
+fn parse_edge(text: &str) -> Option<(Stroke, Head, Option<String>, &str)> {
+    let text = text.strip_prefix('<').unwrap_or(text);
+    let run = text
+        .find(|c: char| !matches!(c, '-' | '=' | '.'))
+        .unwrap_or(text.len());
+    let (op, rest) = text.split_at(run);
+    if op.len() < 2 {
+        return None;
+    }
+
+    // `-- label -->` style: the label sits between two halves of the link
+    if matches!(op, "--" | "==" | "-.") && rest.starts_with(char::is_whitespace) {
+        let closers: &[&str] = match op {
+            "--" => &["-->", "---"],
+            "==" => &["==>", "==="],
+            _ => &[".->", ".-"],
+        };
+        let (at, closer) = closers
+            .iter()
+            .filter_map(|closer| rest.find(closer).map(|at| (at, *closer)))
+            .min_by_key(|(at, _)| *at)?;
+        let label = rest[..at].trim().to_string();
+        let mut after = &rest[at + closer.len()..];
+        // Longer links like `-- label --->`
+        after = after.trim_start_matches(['-', '=', '.']);
+        let (head, after) = match after.strip_prefix('>') {
+            Some(after) => (Head::Arrow, after),
+            None if closer.ends_with('>') => (Head::Arrow, after),
+            None => (Head::None, after),
+        };
+        return Some((stroke_of(op), head, Some(label), after));
+    }
+
+    let (head, rest) = match rest.chars().next() {
+        Some('>') => (Head::Arrow, &rest[1..]),
+        Some('o') if rest[1..].starts_with(char::is_whitespace) => (Head::Circle, &rest[1..]),
+        Some('x') if rest[1..].starts_with(char::is_whitespace) => (Head::Cross, &rest[1..]),
+        _ if op.len() >= 3 => (Head::None, rest),
+        _ => return None,
+    };
+
+    let rest = rest.trim_start();
+    if let Some(labelled) = rest.strip_prefix('|') {
+        let end = labelled.find('|')?;
+        let label = labelled[..end].trim().to_string();
+        return Some((stroke_of(op), head, Some(label), &labelled[end + 1..]));
+    }
+    Some((stroke_of(op), head, None, rest))
+}
+
+fn stroke_of(op: &str) -> Stroke {
+    if op.contains('.') {
+        Stroke::Dotted
+    } else if op.contains('=') {
+        Stroke::Thick
+    } else {
+        Stroke::Solid
+    }
+}
+
+/// Spacing between nodes in the same layer.
+const NODE_GAP: usize = 3;
+/// Rows between layers of a top-down chart.
+const LAYER_GAP_ROWS: usize = 5;
+/// Smallest number of columns between layers of a left-to-right chart.
+const LAYER_GAP_COLUMNS: usize = 6;
+/// Crossing-reduction passes over the layers.
+const ORDERING_SWEEPS: usize = 4;
+
+/// A node or a bend point of a link that spans several layers.
+struct Vertex {
+    node: Option<usize>,
+    layer: usize,
+    x: usize,
+    y: usize,
+    w: usize,
+    h: usize,
+    /// Room kept after the vertex across the flow, for a self-loop
+    margin: usize,
+}
+
+impl Vertex {
+    fn center(&self) -> (usize, usize) {
+        (self.x + self.w / 2, self.y + self.h / 2)
+    }
+}
+
+/// Lay the chart out in layers (longest path from the roots), order each
+/// layer to reduce crossings, then route links through the gaps between
+/// layers.
+fn draw_flowchart(chart: &Flowchart, direction: Direction) -> Canvas {
+    let n = chart.nodes.len();
+
+    // Links that close a cycle are laid out reversed
+    let reversed = back_edges(chart);
+    let layout_edges: Vec<(usize, usize)> = chart
+        .edges
+        .iter()
+        .enumerate()
+        .map(|(i, e)| {
+            if reversed[i] {
+                (e.to, e.from)
+            } else {
+                (e.from, e.to)
+            }
+        })
+        .collect();
+
+    let mut layer = vec![0usize; n];
+    for _ in 0..n {
+        let mut changed = false;
+        for &(from, to) in &layout_edges {
+            if from != to && layer[to] < layer[from] + 1 {
+                layer[to] = layer[from] + 1;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // Self-loops go round the right side of a node in top-down charts, with
+    // their label beside them, and along its bottom in left-to-right ones,
+    // with the label underneath
+    let loop_room = |i: usize| {
+        let edge = chart.edges.iter().find(|e| e.from == i && e.to == i)?;
+        Some(match (direction, &edge.label) {
+            (Direction::Down, Some(label)) => 3 + label.width(),
+            (Direction::Down, None) => 2,
+            (Direction::Right, label) => 1 + usize::from(label.is_some()),
+        })
+    };
+
+    // Real nodes first, then one bend vertex per skipped layer
+    let mut vertices: Vec<Vertex> = chart
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| {
+            let w = node.label.width() + 4;
+            Vertex {
+                node: Some(i),
+                layer: layer[i],
+                x: 0,
+                y: 0,
+                w,
+                h: 3,
+                margin: loop_room(i).unwrap_or(0),
+            }
+        })
+        .collect();
+    let mut paths: Vec<Vec<usize>> = Vec::new();
+    for &(from, to) in &layout_edges {
+        let mut path = vec![from];
+        if from != to {
+            for l in layer[from] + 1..layer[to] {
+                vertices.push(Vertex {
+                    node: None,
+                    layer: l,
+                    x: 0,
+                    y: 0,
+                    w: 1,
+                    h: 1,
+                    margin: 0,
+                });
+                path.push(vertices.len() - 1);
+            }
+        }
+        path.push(to);
+        paths.push(path);
+    }
+
+    let layer_count = layer.iter().max().map_or(0, |l| l + 1);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+    for (v, vertex) in vertices.iter().enumerate() {
+        layers[vertex.layer].push(v);
+    }
+    order_layers(&mut layers, &paths, vertices.len());
+
+    // Size of each layer along the flow, and the gap after it
+    let along = |v: &Vertex| match direction {
+        Direction::Down => v.h,
+        Direction::Right => v.w,
+    };
+    let extents: Vec<usize> = layers
+        .iter()
+        .map(|l| l.iter().map(|&v| along(&vertices[v])).max().unwrap_or(1))
+        .collect();
+    let gaps: Vec<usize> = (0..layer_count)
+        .map(|l| match direction {
+            Direction::Down => LAYER_GAP_ROWS,
+            Direction::Right => {
+                let widest = chart
+                    .edges
+                    .iter()
+                    .zip(&layout_edges)
+                    .filter(|(_, (_, to))| layer[*to] == l + 1)
+                    .filter_map(|(e, _)| e.label.as_ref().map(|s| s.width()))
+                    .max()
+                    .unwrap_or(0);
+                LAYER_GAP_COLUMNS.max(widest + 5)
+            }
+        })
+        .collect();
+
+    // Across the flow, each layer is centred on the widest one
+    let across = |v: &Vertex| match direction {
+        Direction::Down => v.w,
+        Direction::Right => v.h,
+    };
+    let spacing = match direction {
+        Direction::Down => NODE_GAP,
+        Direction::Right => 1,
+    };
+    let breadths: Vec<usize> = layers
+        .iter()
+        .map(|l| {
+            l.iter()
+                .map(|&v| across(&vertices[v]) + vertices[v].margin)
+                .sum::<usize>()
+                + spacing * l.len().saturating_sub(1)
+        })
+        .collect();
+    let breadth = breadths.iter().copied().max().unwrap_or(0);
+
+    let mut start = 0;
+    for (l, members) in layers.iter().enumerate() {
+        let mut offset = (breadth - breadths[l]) / 2;
+        for &v in members {
+            let vertex = &mut vertices[v];
+            // Bend vertices stretch over the whole layer so links pass straight through
+            let (main, cross) = match direction {
+                Direction::Down => (&mut vertex.y, &mut vertex.x),
+                Direction::Right => (&mut vertex.x, &mut vertex.y),
+            };
+            *main = start;
+            *cross = offset;
+            if vertex.node.is_none() {
+                match direction {
+                    Direction::Down => vertex.h = extents[l],
+                    Direction::Right => vertex.w = extents[l],
+                }
+            } else {
+                // Centre smaller nodes within the layer
+                let size = along(vertex);
+                match direction {
+                    Direction::Down => vertex.y += (extents[l] - size) / 2,
+                    Direction::Right => vertex.x += (extents[l] - size) / 2,
+                }
+            }
+            offset += across(&vertices[v]) + vertices[v].margin + spacing;
+        }
+        start += extents[l] + gaps[l];
+    }
+
+    let mut canvas = Canvas::default();
+    let mut heads = Vec::new();
+    let mut labels = Vec::new();
+    let mut exits = Vec::new();
+    let tip_char = |head: Head, arrow: char| match head {
+        Head::None => None,
+        Head::Arrow => Some(arrow),
+        Head::Circle => Some('○'),
+        Head::Cross => Some('×'),
+    };
+
+    for (i, path) in paths.iter().enumerate() {
+        let edge = &chart.edges[i];
+        if edge.from == edge.to {
+            // Out of the node and back in, outside its box
+            let v = &vertices[edge.from];
+            let (x, y, w, h) = (v.x, v.y, v.w, v.h);
+            let (points, exit, label_at) = match direction {
+                Direction::Down => (
+                    vec![
+                        (x + w, y + 1),
+                        (x + w + 1, y + 1),
+                        (x + w + 1, y + h),
+                        (x + w - 2, y + h),
+                    ],
+                    (x + w - 1, y + 1, '├'),
+                    (x + w + 3, y + 1),
+                ),
+                Direction::Right => {
+                    canvas.connect(x + 1, y + h, UP, edge.stroke);
+                    (
+                        vec![(x + 1, y + h), (x + w - 2, y + h)],
+                        (x + 1, y + h - 1, '┬'),
+                        (x + 1, y + h + 1),
+                    )
+                }
+            };
+            canvas.polyline(&points, edge.stroke);
+            exits.push(exit);
+            if let Some(tip) = tip_char(edge.head, '▲') {
+                heads.push((points[points.len() - 1], tip));
+            }
+            if let Some(label) = &edge.label {
+                labels.push((label_at, label.clone()));
+            }
+            continue;
+        }
+        if path.len() < 2 {
+            continue;
+        }
+        for (k, pair) in path.windows(2).enumerate() {
+            let (a, b) = (&vertices[pair[0]], &vertices[pair[1]]);
+            // Reversed links run beside the forward ones in top-down charts
+            let shift = |v: &Vertex| {
+                usize::from(reversed[i] && v.node.is_some() && direction == Direction::Down)
+            };
+            let (ax, ay) = a.center();
+            let (bx, by) = b.center();
+            let (ax, bx) = (ax + shift(a), bx + shift(b));
+            let points = match direction {
+                Direction::Down => {
+                    let (top, bottom) = (a.y + a.h, b.y.saturating_sub(1));
+                    let mid = if reversed[i] {
+                        bottom.saturating_sub(1)
+                    } else {
+                        top + 1
+                    };
+                    vec![(ax, top), (ax, mid), (bx, mid), (bx, bottom)]
+                }
+                Direction::Right => {
+                    let (left, right) = (a.x + a.w, b.x.saturating_sub(1));
+                    let mid = if reversed[i] {
+                        right.saturating_sub(1)
+                    } else {
+                        left + 1
+                    };
+                    vec![(left, ay), (mid, ay), (mid, by), (right, by)]
+                }
+            };
+            if b.node.is_none() {
+                // Carry on through the bend vertex's layer
+                let through = match direction {
+                    Direction::Down => (bx, b.y + b.h - 1),
+                    Direction::Right => (b.x + b.w - 1, by),
+                };
+                canvas.line(points[3], through, edge.stroke);
+            }
+            canvas.polyline(&points, edge.stroke);
+
+            let (first, last) = (k == 0, k + 2 == path.len());
+            if first {
+                let (x, y) = points[0];
+                exits.push(match direction {
+                    Direction::Down => (x, y.saturating_sub(1), '┬'),
+                    Direction::Right => (x.saturating_sub(1), y, '├'),
+                });
+            }
+            // Arrow heads point at the original target, which is the first
+            // vertex for reversed links
+            let tip = match (reversed[i], direction) {
+                (true, _) if !first => None,
+                (false, _) if !last => None,
+                (true, Direction::Down) => Some((points[0], '▲')),
+                (true, Direction::Right) => Some((points[0], '◀')),
+                (false, Direction::Down) => Some((points[3], '▼')),
+                (false, Direction::Right) => Some((points[3], '▶')),
+            };
+            if let Some((at, arrow)) = tip {
+                if let Some(tip) = tip_char(edge.head, arrow) {
+                    heads.push((at, tip));
+                }
+            }
+            if last {
+                if let Some(label) = &edge.label {
+                    let at = match direction {
+                        Direction::Down => (bx + 2, points[1].1 + 1),
+                        Direction::Right => (points[1].0 + 2, by.saturating_sub(1)),
+                    };
+                    labels.push((at, label.clone()));
+                }
+            }
+        }
+    }
+
+    for vertex in &vertices {
+        let Some(i) = vertex.node else {
+            continue;
+        };
+        let node = &chart.nodes[i];
+        let (corners, style) = match node.shape {
+            Shape::Rect => (['┌', '┐', '└', '┘'], border_style()),
+            Shape::Round => (['╭', '╮', '╰', '╯'], border_style()),
+            Shape::Decision => (['◇', '◇', '◇', '◇'], Style::default().fg(Color::Magenta)),
+        };
+        canvas.rect(vertex.x, vertex.y, vertex.w, vertex.h, corners, style);
+        canvas.text(
+            vertex.x + 2,
+            vertex.y + 1,
+            &node.label,
+            Style::default().add_modifier(Modifier::BOLD),
+        );
+    }
+    // Show where links leave a node
+    for (x, y, c) in exits {
+        if let Cell::Char('─' | '│', style) = *canvas.cell(x, y) {
+            canvas.put(x, y, c, style);
+        }
+    }
+    for ((x, y), label) in labels {
+        canvas.text(x, y, &label, label_style());
+    }
+    for ((x, y), tip) in heads {
+        canvas.put(x, y, tip, Style::default().fg(Color::Blue));
+    }
+    canvas
+}
+
+/// Links that point back to a node still being visited in a depth-first
+/// walk, i.e. the ones that close cycles.
+fn back_edges(chart: &Flowchart) -> Vec<bool> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum State {
+        New,
+        Open,
+        Done,
+    }
+    let n = chart.nodes.len();
+    let mut state = vec![State::New; n];
+    let mut reversed = vec![false; chart.edges.len()];
+    for root in 0..n {
+        if state[root] != State::New {
+            continue;
+        }
+        // Iterative DFS: (node, next edge index to look at)
+        let mut stack = vec![(root, 0)];
+        state[root] = State::Open;
+        while let Some(&(node, next)) = stack.last() {
+            let outgoing = chart
+                .edges
+                .iter()
+                .enumerate()
+                .skip(next)
+                .find(|(_, e)| e.from == node);
+            match outgoing {
+                Some((i, edge)) => {
+                    if let Some(top) = stack.last_mut() {
+                        top.1 = i + 1;
+                    }
+                    match state[edge.to] {
+                        State::New => {
+                            state[edge.to] = State::Open;
+                            stack.push((edge.to, 0));
+                        }
+                        State::Open => reversed[i] = edge.from != edge.to,
+                        State::Done => {}
+                    }
+                }
+                None => {
+                    state[node] = State::Done;
+                    stack.pop();
+                }
+            }
+        }
+    }
+    reversed
+}
+
+/// Reorder vertices within layers by the average position of their
+/// neighbours in the layer above, then below (the barycenter heuristic).
+fn order_layers(layers: &mut [Vec<usize>], paths: &[Vec<usize>], count: usize) {
+    let mut up: Vec<Vec<usize>> = vec![Vec::new(); count];
+    let mut down: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for path in paths {
+        for pair in path.windows(2) {
+            if pair[0] != pair[1] {
+                down[pair[0]].push(pair[1]);
+                up[pair[1]].push(pair[0]);
+            }
+        }
+    }
+
+    let mut position = vec![0usize; count];
+    let index = |layers: &[Vec<usize>], position: &mut [usize]| {
+        for layer in layers {
+            for (i, &v) in layer.iter().enumerate() {
+                position[v] = i;
+            }
+        }
+    };
+    index(layers, &mut position);
+
+    for sweep in 0..ORDERING_SWEEPS {
+        let (neighbours, order): (&[Vec<usize>], Vec<usize>) = if sweep % 2 == 0 {
+            (&up, (1..layers.len()).collect())
+        } else {
+            (&down, (0..layers.len().saturating_sub(1)).rev().collect())
+        };
+        for l in order {
+            let mut keyed: Vec<(f64, usize)> = layers[l]
+                .iter()
+                .map(|&v| {
+                    let adjacent = &neighbours[v];
+                    let key = if adjacent.is_empty() {
+                        position[v] as f64
+                    } else {
+                        adjacent.iter().map(|&u| position[u] as f64).sum::<f64>()
+                            / adjacent.len() as f64
+                    };
+                    (key, v)
+                })
+                .collect();
+            keyed.sort_by(|a, b| a.0.total_cmp(&b.0));
+            layers[l] = keyed.into_iter().map(|(_, v)| v).collect();
+            index(layers, &mut position);
+        }
+    }
+}
+
+// Sequence diagrams
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum NotePlacement {
+    LeftOf,
+    RightOf,
+    Over,
+}
+
+enum Event {
+    Message {
+        from: usize,
+        to: usize,
+        text: String,
+        stroke: Stroke,
+        head: Head,
+    },
+    Note {
+        placement: NotePlacement,
+        first: usize,
+        last: usize,
+        text: String,
+    },
+    /// `loop`, `alt`, `else`, `opt` and similar block lines, and `end`
+    Block(String),
+}
+
+#[derive(Default)]
+struct Sequence {
+    /// Ids and display names
+    participants: Vec<(String, String)>,
+    events: Vec<Event>,
+    autonumber: bool,
+}
+
+impl Sequence {
+    fn participant(&mut self, id: &str) -> usize {
+        match self.participants.iter().position(|(p, _)| p == id) {
+            Some(index) => index,
+            None => {
+                self.participants.push((id.to_string(), id.to_string()));
+                self.participants.len() - 1
+            }
+        }
+    }
+}
+
+/// Message arrows, longest first so `-->>` isn't read as `-->`.
+const ARROWS: [(&str, Stroke, Head); 8] = [
+    ("-->>", Stroke::Dotted, Head::Arrow),
+    ("->>", Stroke::Solid, Head::Arrow),
+    ("--x", Stroke::Dotted, Head::Cross),
+    ("-x", Stroke::Solid, Head::Cross),
+    ("--)", Stroke::Dotted, Head::Arrow),
+    ("-)", Stroke::Solid, Head::Arrow),
+    ("-->", Stroke::Dotted, Head::None),
+    ("->", Stroke::Solid, Head::None),
+];
+
+const BLOCK_KEYWORDS: [&str; 10] = [
+    "loop", "alt", "else", "opt", "par", "and", "critical", "option", "break", "rect",
+];
+
+fn parse_sequence(body: &[&str]) -> Option<Sequence> {
+    let mut sequence = Sequence::default();
+    for &line in body {
+        let keyword = line.split_whitespace().next().unwrap_or("");
+        match keyword {
+            "autonumber" => sequence.autonumber = true,
+            "activate" | "deactivate" | "title" => {}
+            "participant" | "actor" => {
+                let rest = line[keyword.len()..].trim();
+                let (id, name) = match rest.split_once(" as ") {
+                    Some((id, name)) => (id.trim(), name.trim()),
+                    None => (rest, rest),
+                };
+                let index = sequence.participant(id);
+                sequence.participants[index].1 = name.to_string();
+            }
+            "end" => sequence.events.push(Event::Block(String::new())),
+            k if BLOCK_KEYWORDS.contains(&k) => {
+                let text = if k == "rect" {
+                    String::new()
+                } else {
+                    line.to_string()
+                };
+                sequence.events.push(Event::Block(text));
+            }
+            k if k.eq_ignore_ascii_case("note") => {
+                let (position, text) = line[keyword.len()..].split_once(':')?;
+                let position = position.trim();
+                let (placement, who) = if let Some(who) = position.strip_prefix("left of ") {
+                    (NotePlacement::LeftOf, who)
+                } else if let Some(who) = position.strip_prefix("right of ") {
+                    (NotePlacement::RightOf, who)
+                } else {
+                    (NotePlacement::Over, position.strip_prefix("over ")?)
+                };
+                let (first, last) = match who.split_once(',') {
+                    Some((a, b)) => (
+                        sequence.participant(a.trim()),
+                        sequence.participant(b.trim()),
+                    ),
+                    None => {
+                        let p = sequence.participant(who.trim());
+                        (p, p)
+                    }
+                };
+                sequence.events.push(Event::Note {
+                    placement,
+                    first: first.min(last),
+                    last: first.max(last),
+                    text: text.trim().to_string(),
+                });
+            }
+            _ => {
+                let (at, arrow, stroke, head) = ARROWS
+                    .iter()
+                    .filter_map(|&(arrow, stroke, head)| {
+                        line.find(arrow).map(|at| (at, arrow, stroke, head))
+                    })
+                    .min_by_key(|&(at, arrow, _, _)| (at, std::cmp::Reverse(arrow.len())))?;
+                let from = line[..at].trim();
+                let rest = &line[at + arrow.len()..];
+                let (to, text) = rest.split_once(':').unwrap_or((rest, ""));
+                let to = to.trim().trim_start_matches(['+', '-']).trim();
+                if from.is_empty() || to.is_empty() {
+                    return None;
+                }
+                let from = sequence.participant(from);
+                let to = sequence.participant(to);
+                sequence.events.push(Event::Message {
+                    from,
+                    to,
+                    text: text.trim().to_string(),
+                    stroke,
+                    head,
+                });
+            }
+        }
+    }
+    (!sequence.participants.is_empty()).then_some(sequence)
+}
+
+/// Participants across the top and bottom with lifelines between, and one
+/// or two rows per message or note in order.
+fn draw_sequence(sequence: &Sequence) -> Canvas {
+    let n = sequence.participants.len();
+    let widths: Vec<usize> = sequence
+        .participants
+        .iter()
+        .map(|(_, name)| name.width() + 4)
+        .collect();
+
+    let mut numbered = Vec::new();
+    let mut number = 0;
+    for event in &sequence.events {
+        if let Event::Message { text, .. } = event {
+            number += 1;
+            numbered.push(if sequence.autonumber {
+                format!("{}. {}", number, text)
+            } else {
+                text.clone()
+            });
+        }
+    }
+
+    // Minimum distances between lifelines: `(left, right, distance)`, with
+    // `None` for the left edge of the diagram
+    let mut constraints: Vec<(Option<usize>, usize, usize)> = Vec::new();
+    for i in 0..n {
+        constraints.push((None, i, widths[i] / 2));
+        if i > 0 {
+            constraints.push((
+                Some(i - 1),
+                i,
+                widths[i - 1].div_ceil(2) + widths[i] / 2 + 2,
+            ));
+        }
+    }
+    let mut messages = numbered.iter();
+    for event in &sequence.events {
+        match event {
+            Event::Message { from, to, .. } => {
+                let text = messages.next().map_or(0, |t| t.width());
+                let (a, b) = (*from.min(to), *from.max(to));
+                if a != b {
+                    constraints.push((Some(a), b, text + 4));
+                } else if a + 1 < n {
+                    constraints.push((Some(a), a + 1, text + 6));
+                }
+            }
+            Event::Note {
+                placement,
+                first,
+                last,
+                text,
+            } => {
+                let w = text.width() + 4;
+                match placement {
+                    NotePlacement::RightOf if first + 1 < n => {
+                        constraints.push((Some(*first), first + 1, w + 4))
+                    }
+                    NotePlacement::LeftOf => match first.checked_sub(1) {
+                        Some(left) => constraints.push((Some(left), *first, w + 4)),
+                        None => constraints.push((None, *first, w + 2)),
+                    },
+                    NotePlacement::Over if first == last => {
+                        constraints.push((None, *first, w / 2));
+                    }
+                    NotePlacement::Over => {
+                        constraints.push((Some(*first), *last, w.saturating_sub(2)))
+                    }
+                    _ => {}
+                }
+            }
+            Event::Block(_) => {}
+        }
+    }
+
+    let mut centers = vec![0usize; n];
+    for _ in 0..=constraints.len() {
+        let mut changed = false;
+        for &(left, right, distance) in &constraints {
+            let base = left.map_or(0, |l| centers[l]);
+            if centers[right] < base + distance {
+                let shift = base + distance - centers[right];
+                for center in &mut centers[right..] {
+                    *center += shift;
+                }
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // Rows: three for the header, then the events, then the footer
+    let mut rows = 3;
+    for event in &sequence.events {
+        rows += match event {
+            Event::Message { from, to, .. } if from == to => 3,
+            Event::Message { .. } => 2,
+            Event::Note { .. } => 3,
+            Event::Block(_) => 1,
+        };
+    }
+    let footer = rows;
+
+    let mut canvas = Canvas::default();
+    let lifeline = Stroke::Dotted;
+    for &center in &centers {
+        canvas.line((center, 3), (center, footer - 1), lifeline);
+    }
+
+    let right_edge = centers
+        .iter()
+        .zip(&widths)
+        .map(|(c, w)| c + w / 2 + 1)
+        .max()
+        .unwrap_or(0);
+
+    let mut y = 3;
+    let mut messages = numbered.iter();
+    let head_style = Style::default().fg(Color::Blue);
+    for event in &sequence.events {
+        match event {
+            Event::Message {
+                from,
+                to,
+                stroke,
+                head,
+                ..
+            } => {
+                let text = messages.next().cloned().unwrap_or_default();
+                let (a, b) = (centers[*from], centers[*to]);
+                if from == to {
+                    canvas.text(a + 2, y, &text, label_style());
+                    canvas.polyline(
+                        &[(a, y + 1), (a + 3, y + 1), (a + 3, y + 2), (a + 1, y + 2)],
+                        *stroke,
+                    );
+                    if *head != Head::None {
+                        canvas.put(a + 1, y + 2, head_char(*head, '◀'), head_style);
+                    }
+                    y += 3;
+                    continue;
+                }
+                let (left, right) = (a.min(b), a.max(b));
+                let offset = (right - left).saturating_sub(text.width()) / 2;
+                canvas.text(left + offset, y, &text, label_style());
+                canvas.line((a, y + 1), (b, y + 1), *stroke);
+                if *head != Head::None {
+                    let (x, tip) = if b > a {
+                        (b - 1, '▶')
+                    } else {
+                        (b + 1, '◀')
+                    };
+                    canvas.put(x, y + 1, head_char(*head, tip), head_style);
+                }
+                y += 2;
+            }
+            Event::Note {
+                placement,
+                first,
+                last,
+                text,
+            } => {
+                let w = text.width() + 4;
+                let x = match placement {
+                    NotePlacement::RightOf => centers[*first] + 2,
+                    NotePlacement::LeftOf => centers[*first].saturating_sub(w + 1),
+                    NotePlacement::Over if first == last => centers[*first].saturating_sub(w / 2),
+                    NotePlacement::Over => centers[*first].saturating_sub(1),
+                };
+                let w = match placement {
+                    NotePlacement::Over if first != last => w.max(centers[*last] + 3 - x),
+                    _ => w,
+                };
+                let style = Style::default().fg(Color::Yellow);
+                canvas.rect(x, y, w, 3, ['┌', '┐', '└', '┘'], style);
+                canvas.text(x + 2, y + 1, text, Style::default());
+                y += 3;
+            }
+            Event::Block(text) => {
+                let style = Style::default().fg(Color::DarkGray);
+                let rule = "┄".repeat(right_edge.max(text.width() + 4));
+                canvas.text(0, y, &rule, style);
+                if !text.is_empty() {
+                    canvas.text(
+                        2,
+                        y,
+                        &format!(" {} ", text),
+                        Style::default().fg(Color::Magenta),
+                    );
+                }
+                y += 1;
+            }
+        }
+    }
+
+    for top in [0, footer] {
+        for (i, (_, name)) in sequence.participants.iter().enumerate() {
+            let x = centers[i].saturating_sub(widths[i] / 2);
+            canvas.rect(x, top, widths[i], 3, ['┌', '┐', '└', '┘'], border_style());
+            canvas.text(
+                x + 2,
+                top + 1,
+                name,
+                Style::default().add_modifier(Modifier::BOLD),
+            );
+        }
+    }
+    canvas
+}
+
+fn head_char(head: Head, arrow: char) -> char {
+    match head {
+        Head::Cross => '×',
+        Head::Circle => '○',
+        _ => arrow,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn draw(source: &str) -> Vec<String> {
+        render_mermaid(source, 200)
+            .expect("diagram should render")
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    fn row_of(lines: &[String], text: &str) -> usize {
+        lines
+            .iter()
+            .position(|line| line.contains(text))
+            .unwrap_or_else(|| panic!("{:?} not drawn in {:#?}", text, lines))
+    }
+
+    fn column_of(lines: &[String], text: &str) -> usize {
+        let line = &lines[row_of(lines, text)];
+        line[..line.find(text).unwrap()].width()
+    }
+
+    #[test]
+    fn parses_chains_shapes_and_edge_labels() {
+        let chart = parse_flowchart(&["A[Start] -->|yes| B{Check} & C(Done)", "B -.-> C"]).unwrap();
+        let labels: Vec<_> = chart
+            .nodes
+            .iter()
+            .map(|n| (n.label.as_str(), n.shape))
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                ("Start", Shape::Rect),
+                ("Check", Shape::Decision),
+                ("Done", Shape::Round)
+            ]
+        );
+        let edges: Vec<_> = chart
+            .edges
+            .iter()
+            .map(|e| (e.from, e.to, e.stroke, e.label.as_deref()))
+            .collect();
+        assert_eq!(
+            edges,
+            [
+                (0, 1, Stroke::Solid, Some("yes")),
+                (0, 2, Stroke::Solid, Some("yes")),
+                (1, 2, Stroke::Dotted, None)
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_text_between_link_halves() {
+        let chart = parse_flowchart(&["A -- ok --> B"]).unwrap();
+        assert_eq!(chart.edges[0].label.as_deref(), Some("ok"));
+        assert_eq!(chart.edges[0].head, Head::Arrow);
+    }
+
+    #[test]
+    fn top_down_puts_targets_below() {
+        let lines = draw("graph TD\n  A[First] --> B[Second]");
+        assert!(row_of(&lines, "First") < row_of(&lines, "Second"));
+        assert!(lines.iter().any(|line| line.contains('▼')));
+    }
+
+    #[test]
+    fn left_to_right_puts_targets_beside() {
+        let lines = draw("flowchart LR\n  A[First] --> B[Second]");
+        assert_eq!(row_of(&lines, "First"), row_of(&lines, "Second"));
+        assert!(column_of(&lines, "First") < column_of(&lines, "Second"));
+        assert!(lines.iter().any(|line| line.contains('▶')));
+    }
+
+    #[test]
+    fn draws_edge_labels() {
+        let lines = draw("graph TD\n  A -->|maybe| B");
+        row_of(&lines, "maybe");
+    }
+
+    #[test]
+    fn cycles_keep_their_layers() {
+        let lines = draw("graph TD\n  A --> B\n  B --> C\n  C --> A");
+        assert!(row_of(&lines, "A") < row_of(&lines, "B"));
+        assert!(row_of(&lines, "B") < row_of(&lines, "C"));
+        // The link back to A points up at it
+        assert!(lines.iter().any(|line| line.contains('▲')));
+    }
+
+    #[test]
+    fn draws_self_loops() {
+        for source in ["graph TD\n  A --> A", "graph LR\n  A -->|again| A"] {
+            let lines = draw(source);
+            assert!(lines.iter().any(|line| line.contains('▲')), "{:#?}", lines);
+        }
+        row_of(&draw("graph LR\n  A -->|again| A"), "again");
+        row_of(&draw("graph TD\n  A -->|again| A"), "again");
+    }
+
+    #[test]
+    fn sequence_notes_sit_by_their_participant() {
+        let lines = draw(
+            "sequenceDiagram\n  Alice->>Bob: Hi\n  Note right of Bob: thinking\n  Note over Alice,Bob: shared",
+        );
+        assert!(column_of(&lines, "thinking") > column_of(&lines, "Bob"));
+        assert!(row_of(&lines, "Hi") < row_of(&lines, "thinking"));
+        assert!(row_of(&lines, "thinking") < row_of(&lines, "shared"));
+        // A note over both spans their lifelines
+        let columns = |row: usize, of: char| -> Vec<usize> {
+            let chars = lines[row].chars().enumerate();
+            chars.filter(|&(_, c)| c == of).map(|(i, _)| i).collect()
+        };
+        let lifelines = columns(row_of(&lines, "Hi"), '┆');
+        let top = row_of(&lines, "shared") - 1;
+        assert!(columns(top, '┌')[0] < lifelines[0]);
+        assert!(columns(top, '┐')[0] > lifelines[1]);
+    }
+
+    #[test]
+    fn unsupported_diagrams_fall_back() {
+        assert!(render_mermaid("pie\n  \"a\": 1", 80).is_none());
+        assert!(render_mermaid("graph RL\n  A --> B", 80).is_none());
+        assert!(render_mermaid("graph TD\n  A[Very long label] --> B", 10).is_none());
+    }
+}
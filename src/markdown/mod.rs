@@ -0,0 +1,15 @@
+pub mod cache;
+pub mod frontmatter;
+pub mod graph;
+pub mod headings;
+pub mod highlight;
+pub mod image;
+pub mod links;
+pub mod loader;
+pub mod math;
+pub mod mermaid;
+pub mod render;
+pub mod slides;
+pub mod source;
+pub mod table;
+pub mod todos;
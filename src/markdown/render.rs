@@ -0,0 +1,1411 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use pulldown_cmark::{
+    Alignment, BlockQuoteKind, BrokenLink, CodeBlockKind, Event, HeadingLevel, LinkType,
+    MetadataBlockKind, Options, Parser, Tag, TagEnd,
+};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Paragraph, Wrap};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::markdown::frontmatter::{parse_toml, parse_yaml};
+use crate::markdown::highlight::highlight_code;
+use crate::markdown::image::{GraphicsImage, InlineImage};
+use crate::markdown::links::LinkRef;
+use crate::markdown::math::latex_to_unicode;
+use crate::markdown::mermaid::render_mermaid;
+use crate::markdown::table::Table;
+use crate::theme::Theme;
+
+/// Markdown rendered to styled terminal lines.
+#[derive(Debug, Clone, Default)]
+pub struct RenderedDocument {
+    pub lines: Vec<Line<'static>>,
+    /// Zero-based source line that each rendered line starts at
+    pub source_lines: Vec<usize>,
+    /// Links in the document; link hint `[n]` refers to entry `n - 1`
+    pub links: Vec<LinkRef>,
+    pub headings: Vec<RenderedHeading>,
+    /// Images drawn by the terminal's graphics protocol over reserved rows
+    pub images: Vec<DocumentImage>,
+    /// Task list checkboxes, in document order
+    pub tasks: Vec<RenderedTask>,
+    /// Wrapped row that each line starts at, for the width it was rendered at
+    pub row_offsets: Vec<usize>,
+    /// Total wrapped rows
+    pub rows: usize,
+    width: u16,
+}
+
+/// A heading and the rendered line it appears on, for the outline.
+#[derive(Debug, Clone)]
+pub struct RenderedHeading {
+    pub level: u8,
+    /// Plain text without inline markup
+    pub text: String,
+    /// Index into `RenderedDocument::lines`
+    pub line: usize,
+}
+
+/// An image the terminal draws itself, over blank lines starting at `line`.
+#[derive(Debug, Clone)]
+pub struct DocumentImage {
+    /// Index into `RenderedDocument::lines`
+    pub line: usize,
+    /// Columns of quote bars and list indentation before the image
+    pub column: u16,
+    pub graphics: Arc<GraphicsImage>,
+}
+
+/// A task list item's checkbox and where it sits in the source.
+#[derive(Debug, Clone)]
+pub struct RenderedTask {
+    /// Index into `RenderedDocument::lines`
+    pub line: usize,
+    /// Byte offset of the `[` that opens the checkbox in the source
+    pub offset: usize,
+    pub checked: bool,
+}
+
+impl RenderedDocument {
+    /// A document with no lines yet, to be filled with [`RenderedDocument::push_line`].
+    pub fn empty(width: u16) -> Self {
+        Self {
+            width,
+            ..Self::default()
+        }
+    }
+
+    /// Plain status lines shown in place of a document.
+    pub fn placeholder(lines: Vec<Line<'static>>, width: u16) -> Self {
+        let mut document = Self::empty(width);
+        for line in lines {
+            document.push_line(line, 0);
+        }
+        document
+    }
+
+    /// Append a line, such as a notice, after the rendered content.
+    pub fn push_line(&mut self, line: Line<'static>, source_line: usize) {
+        self.row_offsets.push(self.rows);
+        self.rows += wrapped_height(&line, self.width);
+        self.lines.push(line);
+        self.source_lines.push(source_line);
+    }
+
+    /// Index of the line shown at wrapped row `row`.
+    pub fn line_at_row(&self, row: usize) -> usize {
+        self.row_offsets
+            .partition_point(|&offset| offset <= row)
+            .saturating_sub(1)
+    }
+
+    /// Index of the rendered line drawn from the source line closest to
+    /// `source_line`, preferring the first one at or after it. Footnotes are
+    /// moved to the end, so source lines are not in order.
+    pub fn line_for_source(&self, source_line: usize) -> Option<usize> {
+        self.source_lines
+            .iter()
+            .enumerate()
+            .min_by_key(|&(i, &l)| (l.abs_diff(source_line), l < source_line, i))
+            .map(|(i, _)| i)
+    }
+}
+
+fn link_hint_style() -> Style {
+    Style::default().fg(Color::Yellow)
+}
+
+/// Add `offset` to the numbers of the link hints on `line`.
+fn renumber_link_hints(line: &mut Line<'static>, offset: usize) {
+    for span in &mut line.spans {
+        if span.style != link_hint_style() {
+            continue;
+        }
+        let number = span
+            .content
+            .strip_prefix('[')
+            .and_then(|rest| rest.strip_suffix(']'))
+            .and_then(|n| n.parse::<usize>().ok());
+        if let Some(number) = number {
+            span.content = format!("[{}]", number + offset).into();
+        }
+    }
+}
+
+/// `n` in superscript digits, for footnote references.
+fn superscript(n: usize) -> String {
+    const DIGITS: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+    n.to_string()
+        .chars()
+        .filter_map(|c| c.to_digit(10).map(|d| DIGITS[d as usize]))
+        .collect()
+}
+
+/// Split spans into rows at most `width` columns wide, breaking after spaces
+/// where possible and dropping the spaces a row would start with.
+fn wrap_spans(spans: Vec<Span<'static>>, width: usize) -> Vec<Vec<Span<'static>>> {
+    let chars: Vec<(char, Style)> = spans
+        .iter()
+        .flat_map(|span| span.content.chars().map(move |c| (c, span.style)))
+        .collect();
+    if width == 0
+        || chars
+            .iter()
+            .map(|(c, _)| c.width().unwrap_or(0))
+            .sum::<usize>()
+            <= width
+    {
+        return vec![spans];
+    }
+
+    let mut rows = Vec::new();
+    let mut row: Vec<(char, Style)> = Vec::new();
+    let mut row_width = 0;
+    // Length of `row` up to and including its last space
+    let mut break_at = None;
+    for (c, style) in chars {
+        let char_width = c.width().unwrap_or(0);
+        if row_width + char_width > width && !row.is_empty() {
+            let tail = match break_at.take() {
+                Some(at) => row.split_off(at),
+                None => Vec::new(),
+            };
+            rows.push(std::mem::replace(&mut row, tail));
+            row_width = row.iter().map(|(c, _)| c.width().unwrap_or(0)).sum();
+            if c == ' ' && row.is_empty() {
+                continue;
+            }
+        }
+        row.push((c, style));
+        row_width += char_width;
+        if c == ' ' {
+            break_at = Some(row.len());
+        }
+    }
+    if !row.is_empty() {
+        rows.push(row);
+    }
+
+    rows.into_iter()
+        .map(|row| {
+            let mut spans: Vec<Span<'static>> = Vec::new();
+            for (c, style) in row {
+                match spans.last_mut() {
+                    Some(span) if span.style == style => span.content.to_mut().push(c),
+                    _ => spans.push(Span::styled(c.to_string(), style)),
+                }
+            }
+            spans
+        })
+        .collect()
+}
+
+/// Rows `line` takes up once wrapped to `width`, matching how the preview
+/// paragraph renders it.
+fn wrapped_height(line: &Line, width: u16) -> usize {
+    Paragraph::new(line.clone())
+        .wrap(Wrap { trim: false })
+        .line_count(width)
+        .max(1)
+}
+
+pub fn parser_options() -> Options {
+    Options::ENABLE_STRIKETHROUGH
+        | Options::ENABLE_GFM
+        | Options::ENABLE_FOOTNOTES
+        | Options::ENABLE_MATH
+        | Options::ENABLE_TASKLISTS
+        | Options::ENABLE_TABLES
+        | Options::ENABLE_WIKILINKS
+        | Options::ENABLE_YAML_STYLE_METADATA_BLOCKS
+        | Options::ENABLE_PLUSES_DELIMITED_METADATA_BLOCKS
+}
+
+/// Render CommonMark (plus GFM tables, strikethrough, task lists, alerts,
+/// footnotes, math and wikilinks) to styled lines. `width` is the number of
+/// columns available in the pane; `wiki_resolves` tells whether a wikilink
+/// destination names an existing note, so broken ones can be marked;
+/// `load_image` decodes an image destination to fit the given columns, or
+/// returns `None` to show its alt text.
+pub fn render_markdown(
+    content: &str,
+    width: u16,
+    wiki_resolves: &dyn Fn(&str) -> bool,
+    load_image: &dyn Fn(&str, u16) -> Option<InlineImage>,
+) -> RenderedDocument {
+    let mut renderer = Renderer::new(content, width as usize, wiki_resolves, load_image);
+    // `[text][ref]` without a matching definition becomes a link marked as
+    // unknown, so it can be shown as broken instead of as literal brackets
+    let broken_reference = |link: BrokenLink| {
+        matches!(link.link_type, LinkType::Reference | LinkType::Collapsed)
+            .then(|| ("".into(), "".into()))
+    };
+    let parser =
+        Parser::new_with_broken_link_callback(content, parser_options(), Some(broken_reference));
+    for (event, range) in parser.into_offset_iter() {
+        renderer.source_offset = range.start;
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+/// A block that contributes a prefix to each of its lines.
+enum Container {
+    /// A quote or GitHub alert, drawn as a box with borders in `color`
+    BlockQuote { color: Color },
+    /// A list item; `marker` is shown on its first line, then replaced by
+    /// `width` spaces so continuation lines align with the item text
+    Item {
+        marker: Option<Span<'static>>,
+        width: usize,
+    },
+}
+
+/// Rendered lines and the entries that refer to them by index. Footnote
+/// definitions are rendered into a body of their own and moved to the end.
+#[derive(Default)]
+struct Body {
+    lines: Vec<Line<'static>>,
+    source_lines: Vec<usize>,
+    links: Vec<LinkRef>,
+    headings: Vec<RenderedHeading>,
+    images: Vec<DocumentImage>,
+    tasks: Vec<RenderedTask>,
+}
+
+struct CodeBlock {
+    language: String,
+    text: String,
+    first_line: usize,
+}
+
+struct Renderer<'a> {
+    width: usize,
+    wiki_resolves: &'a dyn Fn(&str) -> bool,
+    load_image: &'a dyn Fn(&str, u16) -> Option<InlineImage>,
+    line_starts: Vec<usize>,
+    source_offset: usize,
+    lines: Vec<Line<'static>>,
+    source_lines: Vec<usize>,
+    /// Spans of the line being built and the source line it starts at
+    spans: Vec<Span<'static>>,
+    line_source: Option<usize>,
+    styles: Vec<Style>,
+    containers: Vec<Container>,
+    /// Next number for each open list, `None` for bullet lists
+    lists: Vec<Option<u64>>,
+    code_block: Option<CodeBlock>,
+    /// Frontmatter being collected, shown as a card once complete
+    metadata: Option<(MetadataBlockKind, String)>,
+    table: Option<Table>,
+    links: Vec<LinkRef>,
+    headings: Vec<RenderedHeading>,
+    /// Text of the heading being rendered, collected for the outline
+    heading_text: Option<String>,
+    /// Destination and alt text of the image being rendered
+    image: Option<(String, String)>,
+    images: Vec<DocumentImage>,
+    tasks: Vec<RenderedTask>,
+    /// The link being rendered is a reference with no definition
+    broken_link: bool,
+    /// Number of each footnote label, in order of first use
+    footnote_numbers: HashMap<String, usize>,
+    /// Number of the footnote definition being rendered, and the document
+    /// set aside meanwhile
+    outside_footnote: Option<(usize, Body)>,
+    /// Rendered footnote definitions and their numbers
+    footnotes: Vec<(usize, Body)>,
+    /// Separate the next block from the previous one with a blank line
+    needs_blank: bool,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(
+        content: &str,
+        width: usize,
+        wiki_resolves: &'a dyn Fn(&str) -> bool,
+        load_image: &'a dyn Fn(&str, u16) -> Option<InlineImage>,
+    ) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(content.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            width,
+            wiki_resolves,
+            load_image,
+            line_starts,
+            source_offset: 0,
+            lines: Vec::new(),
+            source_lines: Vec::new(),
+            spans: Vec::new(),
+            line_source: None,
+            styles: Vec::new(),
+            containers: Vec::new(),
+            lists: Vec::new(),
+            code_block: None,
+            metadata: None,
+            table: None,
+            links: Vec::new(),
+            headings: Vec::new(),
+            heading_text: None,
+            image: None,
+            images: Vec::new(),
+            tasks: Vec::new(),
+            broken_link: false,
+            footnote_numbers: HashMap::new(),
+            outside_footnote: None,
+            footnotes: Vec::new(),
+            needs_blank: false,
+        }
+    }
+
+    fn source_line(&self) -> usize {
+        self.line_starts
+            .partition_point(|&start| start <= self.source_offset)
+            .saturating_sub(1)
+    }
+
+    fn style(&self) -> Style {
+        self.styles
+            .iter()
+            .fold(Style::default(), |acc, style| acc.patch(*style))
+    }
+
+    fn event(&mut self, event: Event) {
+        if let Some(code) = &mut self.code_block {
+            match event {
+                Event::Text(text) => code.text.push_str(&text),
+                Event::End(TagEnd::CodeBlock) => self.end_code_block(),
+                _ => {}
+            }
+            return;
+        }
+        if let Some((_, alt)) = &mut self.image {
+            match event {
+                Event::Text(text) | Event::Code(text) => alt.push_str(&text),
+                Event::End(TagEnd::Image) => self.end_image(),
+                _ => {}
+            }
+            return;
+        }
+        if let Some((_, text)) = &mut self.metadata {
+            match event {
+                Event::Text(t) => text.push_str(&t),
+                Event::End(TagEnd::MetadataBlock(_)) => self.end_metadata(),
+                _ => {}
+            }
+            return;
+        }
+
+        if let (Some(heading), Event::Text(text) | Event::Code(text)) =
+            (&mut self.heading_text, &event)
+        {
+            heading.push_str(text);
+        }
+
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => self.push_text(&text, self.style()),
+            Event::Code(code) => self.push_text(&code, self.style().patch(Theme::current().code())),
+            Event::InlineMath(math) => match latex_to_unicode(&math) {
+                Some(text) => self.push_text(
+                    &text.replace('\n', " "),
+                    self.style().patch(Style::default().fg(Color::LightMagenta)),
+                ),
+                None => self.push_text(&format!("${}$", math), Theme::current().code()),
+            },
+            Event::DisplayMath(math) => self.display_math(&math),
+            Event::Html(html) | Event::InlineHtml(html) => {
+                let style = Style::default().fg(Color::DarkGray);
+                for (i, part) in html.trim_end_matches('\n').split('\n').enumerate() {
+                    if i > 0 {
+                        self.flush_line();
+                    }
+                    self.push_text(part, style);
+                }
+            }
+            Event::FootnoteReference(label) => {
+                let number = self.footnote_number(&label);
+                self.push_text(&superscript(number), Style::default().fg(Color::Cyan))
+            }
+            Event::SoftBreak => self.push_text(" ", self.style()),
+            Event::HardBreak => self.flush_line(),
+            Event::Rule => {
+                self.start_block();
+                self.push_text(&"─".repeat(40), Style::default().fg(Color::DarkGray));
+                self.flush_line();
+                self.needs_blank = true;
+            }
+            Event::TaskListMarker(checked) => self.task_marker(checked),
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph | Tag::HtmlBlock => self.start_block(),
+            Tag::Heading { level, .. } => {
+                self.start_block();
+                let (depth, color) = match level {
+                    HeadingLevel::H1 => (1, Color::Cyan),
+                    HeadingLevel::H2 => (2, Color::Blue),
+                    HeadingLevel::H3 => (3, Color::Magenta),
+                    HeadingLevel::H4 => (4, Color::Yellow),
+                    HeadingLevel::H5 => (5, Color::Yellow),
+                    HeadingLevel::H6 => (6, Color::Yellow),
+                };
+                let style = Style::default().fg(color).add_modifier(Modifier::BOLD);
+                self.styles.push(style);
+                self.push_text(&format!("{} ", "#".repeat(depth)), style);
+                self.headings.push(RenderedHeading {
+                    level: depth as u8,
+                    text: String::new(),
+                    line: self.lines.len(),
+                });
+                self.heading_text = Some(String::new());
+            }
+            Tag::BlockQuote(kind) => self.start_quote(kind),
+            Tag::CodeBlock(kind) => {
+                self.start_block();
+                // Fenced code starts on the line after the opening fence
+                let (language, first_line) = match kind {
+                    CodeBlockKind::Fenced(info) => (
+                        info.split_whitespace().next().unwrap_or("").to_string(),
+                        self.source_line() + 1,
+                    ),
+                    CodeBlockKind::Indented => (String::new(), self.source_line()),
+                };
+                self.code_block = Some(CodeBlock {
+                    language,
+                    text: String::new(),
+                    first_line,
+                });
+            }
+            Tag::List(start) => {
+                self.flush_line();
+                if self.lists.is_empty() {
+                    self.start_block();
+                }
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush_line();
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        let marker = format!("{}. ", number);
+                        *number += 1;
+                        marker
+                    }
+                    _ => "• ".to_string(),
+                };
+                let width = marker.chars().count();
+                self.containers.push(Container::Item {
+                    marker: Some(Span::styled(marker, Style::default().fg(Color::Cyan))),
+                    width,
+                });
+            }
+            Tag::Emphasis => self
+                .styles
+                .push(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self
+                .styles
+                .push(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => self
+                .styles
+                .push(Style::default().add_modifier(Modifier::CROSSED_OUT)),
+            Tag::Link {
+                link_type: LinkType::WikiLink { .. },
+                dest_url,
+                ..
+            } => {
+                // Broken wikilinks are red so missing notes stand out
+                let color = if (self.wiki_resolves)(&dest_url) {
+                    Color::Blue
+                } else {
+                    Color::Red
+                };
+                self.links.push(LinkRef::Wiki(dest_url.to_string()));
+                self.styles.push(
+                    Style::default()
+                        .fg(color)
+                        .add_modifier(Modifier::UNDERLINED),
+                );
+            }
+            Tag::Link {
+                link_type: LinkType::ReferenceUnknown | LinkType::CollapsedUnknown,
+                ..
+            } => {
+                self.broken_link = true;
+                self.styles.push(Style::default().fg(Color::Red));
+            }
+            Tag::Link { dest_url, .. } => {
+                self.links.push(LinkRef::Markdown(dest_url.to_string()));
+                self.styles.push(
+                    Style::default()
+                        .fg(Color::Blue)
+                        .add_modifier(Modifier::UNDERLINED),
+                );
+            }
+            Tag::Image { dest_url, .. } => {
+                self.image = Some((dest_url.to_string(), String::new()));
+            }
+            Tag::Table(alignments) => {
+                self.start_block();
+                self.table = Some(Table::new(alignments));
+            }
+            Tag::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.start_head();
+                }
+            }
+            Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.start_row();
+                }
+            }
+            Tag::TableCell => {
+                if let Some(table) = &mut self.table {
+                    table.start_cell();
+                }
+            }
+            Tag::FootnoteDefinition(label) => self.start_footnote(&label),
+            Tag::MetadataBlock(kind) => {
+                self.start_block();
+                self.metadata = Some((kind, String::new()));
+            }
+            // Definition lists fall back to their text
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::FootnoteDefinition => self.end_footnote(),
+            TagEnd::Paragraph | TagEnd::HtmlBlock => {
+                self.flush_line();
+                self.needs_blank = true;
+            }
+            TagEnd::Heading(_) => {
+                if let (Some(heading), Some(text)) =
+                    (self.headings.last_mut(), self.heading_text.take())
+                {
+                    heading.text = text.trim().to_string();
+                }
+                self.flush_line();
+                self.styles.pop();
+                self.needs_blank = true;
+            }
+            TagEnd::BlockQuote(_) => self.end_quote(),
+            TagEnd::List(_) => {
+                self.flush_line();
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    self.needs_blank = true;
+                }
+            }
+            TagEnd::Item => {
+                self.flush_line();
+                self.containers.pop();
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.styles.pop();
+            }
+            TagEnd::Link => {
+                self.styles.pop();
+                if std::mem::take(&mut self.broken_link) {
+                    return;
+                }
+                let hint = format!("[{}]", self.links.len());
+                self.push_text(&hint, link_hint_style());
+            }
+            TagEnd::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.end_head();
+                }
+            }
+            TagEnd::Table => self.end_table(),
+            _ => {}
+        }
+    }
+
+    /// Draw an image on its own rows, or its alt text inline if it cannot be
+    /// shown. Images in tables and headings are always shown as alt text.
+    fn end_image(&mut self) {
+        let Some((dest, alt)) = self.image.take() else {
+            return;
+        };
+        let alt_style = Style::default().fg(Color::DarkGray);
+        let available = self.available();
+        let image = if self.table.is_none() && self.heading_text.is_none() {
+            (self.load_image)(&dest, available.min(u16::MAX as usize) as u16)
+        } else {
+            None
+        };
+        let Some(image) = image else {
+            self.push_text(&format!("[image: {}]", alt), alt_style);
+            return;
+        };
+
+        self.flush_line();
+        let source = self.source_line();
+        let first_line = self.lines.len();
+        let quoted = self.in_quote();
+        if let Some(graphics) = image.graphics {
+            self.images.push(DocumentImage {
+                line: first_line,
+                column: self.prefix_width() as u16,
+                graphics,
+            });
+        }
+        for (i, line) in image.lines.into_iter().enumerate() {
+            // Reserved rows stay empty, apart from the alt text that shows
+            // while the image is partly scrolled out of view
+            let mut spans = match (line.spans.is_empty(), i) {
+                (true, 0) => {
+                    let mut spans = self.prefix();
+                    spans.push(Span::styled(format!("[image: {}]", alt), alt_style));
+                    spans
+                }
+                (true, _) if !quoted => Vec::new(),
+                _ => {
+                    let mut spans = self.prefix();
+                    spans.extend(line.spans);
+                    spans
+                }
+            };
+            self.close_quotes(&mut spans);
+            self.lines.push(Line::from(spans));
+            self.source_lines.push(source);
+        }
+    }
+
+    /// Display math on lines of its own, centered in the pane. TeX that
+    /// can't be converted is shown as code.
+    fn display_math(&mut self, tex: &str) {
+        self.flush_line();
+        match latex_to_unicode(tex) {
+            Some(text) => {
+                let available = self.available();
+                for line in text.split('\n') {
+                    let padding = available.saturating_sub(line.width()) / 2;
+                    self.push_text(&" ".repeat(padding), Style::default());
+                    self.push_text(line, Style::default().fg(Color::LightMagenta));
+                    self.flush_line();
+                }
+            }
+            None => {
+                let code = Theme::current().code();
+                for line in tex.trim().split('\n') {
+                    self.push_text("  ", Style::default());
+                    self.push_text(line.trim_end(), code);
+                    self.flush_line();
+                }
+            }
+        }
+    }
+
+    fn footnote_number(&mut self, label: &str) -> usize {
+        let next = self.footnote_numbers.len() + 1;
+        *self
+            .footnote_numbers
+            .entry(label.to_lowercase())
+            .or_insert(next)
+    }
+
+    /// Replace the rendered lines and their entries with `body`, returning
+    /// the previous ones.
+    fn swap_body(&mut self, body: Body) -> Body {
+        Body {
+            lines: std::mem::replace(&mut self.lines, body.lines),
+            source_lines: std::mem::replace(&mut self.source_lines, body.source_lines),
+            links: std::mem::replace(&mut self.links, body.links),
+            headings: std::mem::replace(&mut self.headings, body.headings),
+            images: std::mem::replace(&mut self.images, body.images),
+            tasks: std::mem::replace(&mut self.tasks, body.tasks),
+        }
+    }
+
+    /// Render a footnote definition on its own, numbered like a list item,
+    /// to be gathered at the end of the document.
+    fn start_footnote(&mut self, label: &str) {
+        self.flush_line();
+        let number = self.footnote_number(label);
+        let document = self.swap_body(Body::default());
+        self.outside_footnote = Some((number, document));
+        let marker = format!("{}. ", number);
+        self.containers.push(Container::Item {
+            width: marker.chars().count(),
+            marker: Some(Span::styled(marker, Style::default().fg(Color::Cyan))),
+        });
+        self.needs_blank = false;
+    }
+
+    fn end_footnote(&mut self) {
+        self.flush_line();
+        self.containers.pop();
+        if let Some((number, document)) = self.outside_footnote.take() {
+            let footnote = self.swap_body(document);
+            self.footnotes.push((number, footnote));
+        }
+        self.needs_blank = true;
+    }
+
+    /// Open a box for a quote: a plain top border for ordinary quotes, or
+    /// the icon and title of a GitHub alert such as `> [!WARNING]`.
+    fn start_quote(&mut self, kind: Option<BlockQuoteKind>) {
+        self.start_block();
+        let (color, title, style) = match kind {
+            None => (
+                Color::DarkGray,
+                None,
+                Style::default()
+                    .fg(Color::DarkGray)
+                    .add_modifier(Modifier::ITALIC),
+            ),
+            Some(kind) => {
+                let (color, title) = match kind {
+                    BlockQuoteKind::Note => (Color::Blue, "ℹ Note"),
+                    BlockQuoteKind::Tip => (Color::Green, "💡 Tip"),
+                    BlockQuoteKind::Important => (Color::Magenta, "❗ Important"),
+                    BlockQuoteKind::Warning => (Color::Yellow, "⚠ Warning"),
+                    BlockQuoteKind::Caution => (Color::Red, "⛔ Caution"),
+                };
+                (color, Some(title), Style::default())
+            }
+        };
+
+        let border = Style::default().fg(color);
+        let available = self.available().max(2);
+        self.push_text("╭─", border);
+        let mut used = 2;
+        if let Some(title) = title {
+            let title = format!(" {} ", title);
+            used += title.width();
+            self.push_text(&title, border.add_modifier(Modifier::BOLD));
+        }
+        self.push_text(&"─".repeat(available.saturating_sub(used + 1)), border);
+        self.push_text("╮", border);
+        self.flush_line();
+
+        self.containers.push(Container::BlockQuote { color });
+        self.styles.push(style);
+    }
+
+    fn end_quote(&mut self) {
+        self.flush_line();
+        self.styles.pop();
+        let color = match self.containers.pop() {
+            Some(Container::BlockQuote { color }) => color,
+            _ => Color::DarkGray,
+        };
+        // The end event points back at the quote's first line, so the border
+        // belongs with the last line drawn
+        self.line_source = self.source_lines.last().copied();
+        let available = self.available().max(2);
+        let border = Style::default().fg(color);
+        self.push_text("╰", border);
+        self.push_text(&"─".repeat(available - 2), border);
+        self.push_text("╯", border);
+        self.flush_line();
+        self.needs_blank = true;
+    }
+
+    /// Draw a task list checkbox in place of a bullet item's `• `, or after
+    /// the number of an ordered item.
+    fn task_marker(&mut self, checked: bool) {
+        let (symbol, color) = if checked {
+            ("☑ ", Color::Green)
+        } else {
+            ("☐ ", Color::Cyan)
+        };
+        self.tasks.push(RenderedTask {
+            line: self.lines.len(),
+            offset: self.source_offset,
+            checked,
+        });
+        let bullet = matches!(self.lists.last(), Some(None));
+        if let (
+            true,
+            Some(Container::Item {
+                marker: marker @ Some(_),
+                width,
+            }),
+        ) = (bullet, self.containers.last_mut())
+        {
+            *marker = Some(Span::styled(symbol, Style::default().fg(color)));
+            *width = 2;
+        } else {
+            self.push_text(symbol, Style::default().fg(color));
+        }
+    }
+
+    fn end_table(&mut self) {
+        let Some(table) = self.table.take() else {
+            return;
+        };
+        let available = self.available();
+        for line in table.render(available) {
+            self.spans = line.spans;
+            self.flush_line();
+        }
+        self.needs_blank = true;
+    }
+
+    /// Show frontmatter as a key/value card, or as dim raw text if it does
+    /// not parse.
+    fn end_metadata(&mut self) {
+        let Some((kind, text)) = self.metadata.take() else {
+            return;
+        };
+        let parsed = match kind {
+            MetadataBlockKind::YamlStyle => parse_yaml(&text),
+            MetadataBlockKind::PlusesStyle => parse_toml(&text),
+        };
+        // The card belongs to the opening fence
+        let first_line = self.source_line();
+
+        match parsed {
+            Ok(frontmatter) if !frontmatter.fields.is_empty() => {
+                let mut table = Table::new(vec![Alignment::Right, Alignment::Left]);
+                for (key, value) in frontmatter.fields {
+                    table.start_row();
+                    table.start_cell();
+                    table.push(Span::styled(
+                        key,
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD),
+                    ));
+                    table.start_cell();
+                    table.push(Span::raw(value));
+                }
+                self.table = Some(table);
+                self.line_source = Some(first_line);
+                self.end_table();
+                return;
+            }
+            Ok(_) => return,
+            Err(_) => {}
+        }
+
+        for (i, line) in text.trim_end_matches('\n').split('\n').enumerate() {
+            self.line_source = Some(first_line + 1 + i);
+            self.push_text(line, Style::default().fg(Color::DarkGray));
+            self.flush_line();
+        }
+        self.needs_blank = true;
+    }
+
+    /// Columns taken up by quote bars and list indentation.
+    fn prefix_width(&self) -> usize {
+        self.containers
+            .iter()
+            .map(|c| match c {
+                Container::BlockQuote { .. } => 2,
+                Container::Item { width, .. } => *width,
+            })
+            .sum()
+    }
+
+    fn in_quote(&self) -> bool {
+        self.containers
+            .iter()
+            .any(|c| matches!(c, Container::BlockQuote { .. }))
+    }
+
+    /// Columns left for content between the enclosing quotes' borders.
+    fn available(&self) -> usize {
+        let quotes = self
+            .containers
+            .iter()
+            .filter(|c| matches!(c, Container::BlockQuote { .. }))
+            .count();
+        self.width.saturating_sub(self.prefix_width() + 2 * quotes)
+    }
+
+    /// Pad a row inside quotes out to their right borders and close it with
+    /// them, innermost first.
+    fn close_quotes(&self, spans: &mut Vec<Span<'static>>) {
+        let borders: Vec<Span<'static>> = self
+            .containers
+            .iter()
+            .rev()
+            .filter_map(|c| match c {
+                Container::BlockQuote { color } => {
+                    Some(Span::styled(" │", Style::default().fg(*color)))
+                }
+                Container::Item { .. } => None,
+            })
+            .collect();
+        if borders.is_empty() {
+            return;
+        }
+        let used: usize = spans.iter().map(|span| span.width()).sum();
+        let padding = self.width.saturating_sub(used + 2 * borders.len());
+        if padding > 0 {
+            spans.push(Span::raw(" ".repeat(padding)));
+        }
+        spans.extend(borders);
+    }
+
+    fn end_code_block(&mut self) {
+        let Some(code) = self.code_block.take() else {
+            return;
+        };
+
+        if !code.language.is_empty() {
+            self.line_source = Some(code.first_line.saturating_sub(1));
+            self.push_text(&code.language, Style::default().fg(Color::DarkGray));
+            self.flush_line();
+        }
+
+        let text = code.text.trim_end_matches('\n').replace('\t', "    ");
+
+        if code.language == "mermaid" {
+            let available = self.available();
+            if let Some(diagram) = render_mermaid(&text, available) {
+                for line in diagram {
+                    self.line_source = Some(code.first_line);
+                    self.spans = line.spans;
+                    // Keep blank rows of the diagram
+                    if self.spans.is_empty() {
+                        self.push_text(" ", Style::default());
+                    }
+                    self.flush_line();
+                }
+                self.needs_blank = true;
+                return;
+            }
+        }
+
+        let highlighted = highlight_code(&text, &code.language);
+        // Blocks sit on the terminal background, like highlighted ones
+        let plain = Style {
+            bg: None,
+            ..Theme::current().code()
+        };
+        for (i, line) in text.split('\n').enumerate() {
+            self.line_source = Some(code.first_line + i);
+            self.push_text("  ", plain);
+            match highlighted.as_ref().and_then(|lines| lines.get(i)) {
+                Some(spans) => self.spans.extend(spans.iter().cloned()),
+                None => self.push_text(line, plain),
+            }
+            self.flush_line();
+        }
+        self.needs_blank = true;
+    }
+
+    /// Begin a block, separating it from the previous one if needed.
+    fn start_block(&mut self) {
+        self.flush_line();
+        if self.needs_blank && !self.lines.is_empty() {
+            // Only quote bars carry over to blank lines; list indentation is dropped
+            let mut bars = self
+                .containers
+                .iter()
+                .filter_map(|c| match c {
+                    Container::BlockQuote { color } => {
+                        Some(Span::styled("│ ", Style::default().fg(*color)))
+                    }
+                    Container::Item { .. } => None,
+                })
+                .collect::<Vec<_>>();
+            self.close_quotes(&mut bars);
+            self.lines.push(Line::from(bars));
+            // Blank separators belong to the block above
+            let previous = self.source_lines.last().copied().unwrap_or(0);
+            self.source_lines.push(previous);
+        }
+        self.needs_blank = false;
+    }
+
+    fn push_text(&mut self, text: &str, style: Style) {
+        if let Some(table) = &mut self.table {
+            if !text.is_empty() {
+                table.push(Span::styled(text.to_string(), style));
+            }
+            return;
+        }
+        if self.line_source.is_none() {
+            self.line_source = Some(self.source_line());
+        }
+        if !text.is_empty() {
+            self.spans.push(Span::styled(text.to_string(), style));
+        }
+    }
+
+    /// Prefix spans for a new line from the enclosing quotes and list items.
+    /// Pending list markers are shown (and consumed) on this line.
+    fn prefix(&mut self) -> Vec<Span<'static>> {
+        let mut prefix = Vec::new();
+        for container in &mut self.containers {
+            match container {
+                Container::BlockQuote { color } => {
+                    prefix.push(Span::styled("│ ", Style::default().fg(*color)))
+                }
+                Container::Item { marker, width } => match marker.take() {
+                    Some(marker) => prefix.push(marker),
+                    None => prefix.push(Span::raw(" ".repeat(*width))),
+                },
+            }
+        }
+        prefix
+    }
+
+    fn flush_line(&mut self) {
+        if self.spans.is_empty() {
+            return;
+        }
+        let source = self
+            .line_source
+            .take()
+            .unwrap_or_else(|| self.source_line());
+
+        // Inside quotes the text is wrapped here rather than by the paragraph,
+        // so every row keeps its border
+        let content = std::mem::take(&mut self.spans);
+        let quoted = self.in_quote();
+        let rows = if quoted {
+            wrap_spans(content, self.available())
+        } else {
+            vec![content]
+        };
+
+        for mut row in rows {
+            let mut spans = self.prefix();
+            spans.append(&mut row);
+            self.close_quotes(&mut spans);
+            // Whitespace-only lines wrap to two rows in ratatui, so keep them empty
+            if spans.iter().all(|span| span.content.trim().is_empty()) {
+                spans.clear();
+            }
+            self.lines.push(Line::from(spans));
+            self.source_lines.push(source);
+        }
+    }
+
+    /// Gather the footnote definitions under a rule at the end, in the
+    /// order they are first referenced.
+    fn append_footnotes(&mut self) {
+        if self.footnotes.is_empty() {
+            return;
+        }
+        let mut footnotes = std::mem::take(&mut self.footnotes);
+        footnotes.sort_by_key(|(number, _)| *number);
+
+        self.needs_blank = true;
+        self.start_block();
+        self.line_source = self.source_lines.last().copied();
+        self.push_text(&"─".repeat(40), Style::default().fg(Color::DarkGray));
+        self.flush_line();
+
+        for (_, footnote) in footnotes {
+            let offset = self.lines.len();
+            // Link hints carry on from the ones before
+            let link_offset = self.links.len();
+            self.lines
+                .extend(footnote.lines.into_iter().map(|mut line| {
+                    if link_offset > 0 {
+                        renumber_link_hints(&mut line, link_offset);
+                    }
+                    line
+                }));
+            self.links.extend(footnote.links);
+            self.source_lines.extend(footnote.source_lines);
+            self.headings
+                .extend(footnote.headings.into_iter().map(|mut heading| {
+                    heading.line += offset;
+                    heading
+                }));
+            self.images
+                .extend(footnote.images.into_iter().map(|mut image| {
+                    image.line += offset;
+                    image
+                }));
+            self.tasks
+                .extend(footnote.tasks.into_iter().map(|mut task| {
+                    task.line += offset;
+                    task
+                }));
+        }
+    }
+
+    fn finish(mut self) -> RenderedDocument {
+        self.flush_line();
+        self.append_footnotes();
+        let width = self.width as u16;
+        let mut row_offsets = Vec::with_capacity(self.lines.len());
+        let mut rows = 0;
+        for line in &self.lines {
+            row_offsets.push(rows);
+            rows += wrapped_height(line, width);
+        }
+        RenderedDocument {
+            lines: self.lines,
+            source_lines: self.source_lines,
+            links: self.links,
+            headings: self.headings,
+            images: self.images,
+            tasks: self.tasks,
+            row_offsets,
+            rows,
+            width,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(content: &str) -> RenderedDocument {
+        render_markdown(content, 80, &|_| true, &|_, _| None)
+    }
+
+    fn text(line: &Line) -> String {
+        line.spans
+            .iter()
+            .map(|span| span.content.as_ref())
+            .collect()
+    }
+
+    fn texts(content: &str) -> Vec<String> {
+        render(content).lines.iter().map(text).collect()
+    }
+
+    /// The span holding `needle` on any line.
+    fn span_with<'a>(document: &'a RenderedDocument, needle: &str) -> &'a Span<'static> {
+        document
+            .lines
+            .iter()
+            .flat_map(|line| &line.spans)
+            .find(|span| span.content.contains(needle))
+            .unwrap_or_else(|| panic!("no span containing {:?}", needle))
+    }
+
+    fn is_italic(span: &Span) -> bool {
+        span.style.add_modifier.contains(Modifier::ITALIC)
+    }
+
+    #[test]
+    fn quotes_are_closed_boxes() {
+        let lines: Vec<String> = render_markdown(
+            "> [!NOTE]\n> a long line that wraps inside the box\n>\n>> nested\n",
+            20,
+            &|_| true,
+            &|_, _| None,
+        )
+        .lines
+        .iter()
+        .map(text)
+        .collect();
+        assert!(lines.len() > 6, "{:#?}", lines);
+        for line in &lines {
+            assert_eq!(line.width(), 20, "{:?}", line);
+        }
+        assert!(lines[0].starts_with('╭') && lines[0].ends_with('╮'));
+        assert!(lines[1..lines.len() - 1].iter().all(|l| l.ends_with('│')));
+        assert!(lines[lines.len() - 1].starts_with('╰') && lines[lines.len() - 1].ends_with('╯'));
+    }
+
+    #[test]
+    fn setext_headings() {
+        let document = render("Foo *bar*\n=========\n\nFoo\n---\n");
+        assert_eq!(
+            document.lines.iter().map(text).collect::<Vec<_>>(),
+            ["# Foo bar", "", "## Foo"]
+        );
+        let levels: Vec<u8> = document.headings.iter().map(|h| h.level).collect();
+        assert_eq!(levels, [1, 2]);
+        assert_eq!(document.headings[0].text, "Foo bar");
+        assert!(is_italic(span_with(&document, "bar")));
+    }
+
+    #[test]
+    fn setext_underline_after_multiple_lines() {
+        assert_eq!(texts("Foo\nbar\n---\n"), ["## Foo bar"]);
+    }
+
+    #[test]
+    fn thematic_break_is_not_a_heading_after_blank_line() {
+        assert_eq!(texts("Foo\n\n---\n"), ["Foo", "", &"─".repeat(40)]);
+    }
+
+    #[test]
+    fn indented_code() {
+        let document = render("text\n\n    a simple\n      indented code block\n");
+        assert_eq!(
+            document.lines.iter().map(text).collect::<Vec<_>>(),
+            ["text", "", "  a simple", "    indented code block"]
+        );
+        assert_eq!(document.source_lines, [0, 0, 2, 3]);
+    }
+
+    #[test]
+    fn indented_code_does_not_interrupt_paragraph() {
+        assert_eq!(texts("Foo\n    bar\n"), ["Foo bar"]);
+    }
+
+    #[test]
+    fn tilde_fence() {
+        assert_eq!(texts("~~~\n<\n >\n~~~\n"), ["  <", "   >"]);
+    }
+
+    #[test]
+    fn fence_closes_only_on_matching_marker() {
+        assert_eq!(texts("~~~\naaa\n```\n~~~\n"), ["  aaa", "  ```"]);
+        assert_eq!(texts("````\naaa\n```\n``````\n"), ["  aaa", "  ```"]);
+    }
+
+    #[test]
+    fn fence_language_is_shown() {
+        assert_eq!(texts("```text\nplain\n```\n"), ["text", "  plain"]);
+    }
+
+    #[test]
+    fn nested_lists() {
+        assert_eq!(
+            texts("- a\n  - b\n    - c\n- d\n"),
+            ["• a", "  • b", "    • c", "• d"]
+        );
+    }
+
+    #[test]
+    fn list_item_continuation_aligns_with_text() {
+        assert_eq!(texts("1. one\n\n   two\n"), ["1. one", "", "   two"]);
+    }
+
+    #[test]
+    fn ordered_lists_keep_their_numbers() {
+        assert_eq!(
+            texts("1. one\n2. two\n3. three\n"),
+            ["1. one", "2. two", "3. three"]
+        );
+        assert_eq!(texts("7) seven\n8) eight\n"), ["7. seven", "8. eight"]);
+    }
+
+    #[test]
+    fn ordered_list_inside_bullet_list() {
+        assert_eq!(texts("- a\n  1. b\n  2. c\n"), ["• a", "  1. b", "  2. c"]);
+    }
+
+    #[test]
+    fn emphasis_across_lines() {
+        let document = render("*foo\nbar*\n");
+        assert_eq!(text(&document.lines[0]), "foo bar");
+        assert!(document.lines[0].spans.iter().all(is_italic));
+    }
+
+    #[test]
+    fn underscore_emphasis() {
+        let document = render("_foo_ and __bar__\n");
+        assert_eq!(text(&document.lines[0]), "foo and bar");
+        assert!(is_italic(span_with(&document, "foo")));
+        assert!(span_with(&document, "bar")
+            .style
+            .add_modifier
+            .contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn intraword_underscores_are_literal() {
+        let document = render("snake_case_name\n");
+        assert_eq!(text(&document.lines[0]), "snake_case_name");
+        assert!(!document.lines[0].spans.iter().any(is_italic));
+    }
+
+    #[test]
+    fn escaped_asterisks_are_literal() {
+        let document = render("\\*not emphasized*\n");
+        assert_eq!(text(&document.lines[0]), "*not emphasized*");
+        assert!(!document.lines[0].spans.iter().any(is_italic));
+    }
+
+    #[test]
+    fn hard_and_soft_breaks() {
+        assert_eq!(texts("foo  \nbar\n"), ["foo", "bar"]);
+        assert_eq!(texts("foo\\\nbar\n"), ["foo", "bar"]);
+        assert_eq!(texts("foo\nbar\n"), ["foo bar"]);
+    }
+
+    #[test]
+    fn links_get_hints() {
+        let document = render("[a](x.md) and [b](y.md)\n");
+        assert_eq!(text(&document.lines[0]), "a[1] and b[2]");
+        assert_eq!(document.links.len(), 2);
+    }
+
+    #[test]
+    fn line_for_source_finds_block() {
+        let document = render("# One\n\npara\n\n# Two\n");
+        assert_eq!(document.line_for_source(4), Some(4));
+        assert_eq!(document.line_for_source(2), Some(2));
+    }
+
+    #[test]
+    fn line_for_source_finds_footnotes() {
+        let document = render("Text[^1]\n\n[^1]: The note\n\nMore\n\nEnd\n");
+        let note = document
+            .lines
+            .iter()
+            .position(|line| text(line).contains("The note"))
+            .unwrap();
+        assert_eq!(document.line_for_source(2), Some(note));
+        assert_eq!(
+            text(&document.lines[document.line_for_source(3).unwrap()]),
+            "More"
+        );
+    }
+
+    #[test]
+    fn footnote_links_follow_body_links() {
+        let document = render("[^1]: See [c](c.md)\n\nText[^1] and [a](a.md) [b](b.md)\n");
+        let lines: Vec<String> = document.lines.iter().map(text).collect();
+        assert!(
+            lines.iter().any(|l| l.ends_with("a[1] b[2]")),
+            "{:#?}",
+            lines
+        );
+        assert!(
+            lines.iter().any(|l| l.ends_with("See c[3]")),
+            "{:#?}",
+            lines
+        );
+        let targets: Vec<_> = document
+            .links
+            .iter()
+            .map(|link| match link {
+                LinkRef::Markdown(dest) | LinkRef::Wiki(dest) => dest.as_str(),
+            })
+            .collect();
+        assert_eq!(targets, ["a.md", "b.md", "c.md"]);
+    }
+
+    #[test]
+    fn task_offsets_point_at_the_checkbox() {
+        let content = "# Tasks\r\n\r\n- [ ] one\r\n  * [x] nested\r\n\r\n> - [X] quoted\r\n";
+        let document = render(content);
+        let boxes: Vec<(&str, bool)> = document
+            .tasks
+            .iter()
+            .map(|task| (&content[task.offset..task.offset + 3], task.checked))
+            .collect();
+        assert_eq!(boxes, [("[ ]", false), ("[x]", true), ("[X]", true)]);
+    }
+
+    #[test]
+    fn code_uses_the_theme() {
+        let theme = Theme::current().code();
+        let document =
+            render("Call `run` with $\\unknown{x}$.\n\n```no-such-language\nplain code\n```\n");
+        assert_eq!(span_with(&document, "run").style, theme);
+        assert_eq!(span_with(&document, "unknown").style, theme);
+        // Unhighlighted blocks keep the code colour on the terminal background
+        let block = span_with(&document, "plain code").style;
+        assert_eq!((block.fg, block.bg), (theme.fg, None));
+    }
+}
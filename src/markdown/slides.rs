@@ -0,0 +1,167 @@
+use crate::markdown::frontmatter::body_lines;
+
+/// One slide of a presentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slide {
+    /// Markdown of the slide, without its speaker notes
+    pub source: String,
+    /// Text of the slide's HTML comments
+    pub notes: Vec<String>,
+    /// Zero-based source line the slide starts at
+    pub line: usize,
+}
+
+/// Split a document into slides. Documents with `---` separators (a line of
+/// their own after a blank line, so setext underlines don't count) are split
+/// there; otherwise each H1 or H2 starts a new slide. Frontmatter is skipped
+/// and slides with nothing to show are dropped.
+pub fn split_slides(content: &str) -> Vec<Slide> {
+    let lines: Vec<(usize, &str, bool)> = body_lines(content).collect();
+
+    let mut separators = Vec::new();
+    let mut headings = Vec::new();
+    for (i, &(_, line, in_code)) in lines.iter().enumerate() {
+        if in_code {
+            continue;
+        }
+        let after_blank = i == 0 || lines[i - 1].1.trim().is_empty();
+        if line.trim() == "---" && after_blank {
+            separators.push(i);
+        } else if is_slide_heading(line) {
+            headings.push(i);
+        }
+    }
+
+    // (first line, end line) of each slide, as indices into `lines`
+    let mut ranges = Vec::new();
+    if separators.is_empty() {
+        let mut from = 0;
+        for &heading in &headings {
+            if heading > from {
+                ranges.push((from, heading));
+            }
+            from = heading;
+        }
+        ranges.push((from, lines.len()));
+    } else {
+        let mut from = 0;
+        for &separator in &separators {
+            ranges.push((from, separator));
+            from = separator + 1;
+        }
+        ranges.push((from, lines.len()));
+    }
+
+    ranges
+        .into_iter()
+        .filter_map(|(from, to)| {
+            let (source, notes) = take_notes(&lines[from..to]);
+            (!source.trim().is_empty()).then(|| Slide {
+                source,
+                notes,
+                line: lines.get(from).map_or(0, |&(line, _, _)| line),
+            })
+        })
+        .collect()
+}
+
+/// An ATX H1 or H2 outside of indented code.
+fn is_slide_heading(line: &str) -> bool {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let trimmed = line.trim();
+    let hashes = trimmed.len() - trimmed.trim_start_matches('#').len();
+    indent < 4
+        && (1..=2).contains(&hashes)
+        && (trimmed.len() == hashes || trimmed[hashes..].starts_with([' ', '\t']))
+}
+
+/// Remove HTML comments from the slide's lines, returning the remaining
+/// markdown and the comments' text. Lines that held only a comment are
+/// dropped; comments inside fenced code are left alone.
+fn take_notes(lines: &[(usize, &str, bool)]) -> (String, Vec<String>) {
+    let mut source = String::new();
+    let mut notes = Vec::new();
+    let mut note: Option<String> = None;
+
+    for &(_, line, in_code) in lines {
+        if in_code && note.is_none() {
+            source.push_str(line);
+            source.push('\n');
+            continue;
+        }
+
+        let mut kept = String::new();
+        let mut rest = line;
+        let mut had_comment = note.is_some();
+        loop {
+            match &mut note {
+                Some(text) => match rest.find("-->") {
+                    Some(end) => {
+                        text.push_str(&rest[..end]);
+                        let text = text.trim().to_string();
+                        if !text.is_empty() {
+                            notes.push(text);
+                        }
+                        note = None;
+                        rest = &rest[end + 3..];
+                    }
+                    None => {
+                        text.push_str(rest);
+                        text.push('\n');
+                        break;
+                    }
+                },
+                None => match rest.find("<!--") {
+                    Some(begin) => {
+                        kept.push_str(&rest[..begin]);
+                        note = Some(String::new());
+                        had_comment = true;
+                        rest = &rest[begin + 4..];
+                    }
+                    None => {
+                        kept.push_str(rest);
+                        break;
+                    }
+                },
+            }
+        }
+
+        if !(had_comment && kept.trim().is_empty()) {
+            source.push_str(&kept);
+            source.push('\n');
+        }
+    }
+
+    // A comment left open runs to the end of the slide
+    if let Some(text) = note.map(|text| text.trim().to_string()) {
+        if !text.is_empty() {
+            notes.push(text);
+        }
+    }
+    (source, notes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_on_separators_outside_code() {
+        let content = "---\ntitle: Deck\n---\n# One\n\n```\n\n---\n```\n\n---\n\n# Two\n";
+        let slides = split_slides(content);
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].line, 3);
+        assert!(slides[0].source.contains("```\n\n---\n```"));
+        assert_eq!(slides[1].line, 11);
+    }
+
+    #[test]
+    fn takes_notes_outside_code() {
+        let content = "# One\n<!-- say hi -->\n~~~\n<!-- shown -->\n~~~\n## Two\n";
+        let slides = split_slides(content);
+        assert_eq!(slides.len(), 2);
+        assert_eq!(slides[0].notes, ["say hi"]);
+        assert!(slides[0].source.contains("<!-- shown -->"));
+        assert_eq!(slides[1].line, 5);
+    }
+}
@@ -0,0 +1,59 @@
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+
+use crate::markdown::render::RenderedDocument;
+
+/// Tabs are shown expanded to this many columns.
+const TAB_WIDTH: usize = 4;
+
+/// The document's literal text with a line-number gutter. Tabs are shown as
+/// `→` and trailing whitespace as `·` so they can be spotted before editing.
+pub fn render_source(content: &str, width: u16) -> RenderedDocument {
+    let gutter_width = content.lines().count().max(1).to_string().len();
+    let gutter_style = Style::default().fg(Color::DarkGray);
+    let marker_style = Style::default().fg(Color::DarkGray);
+    let trailing_style = Style::default().fg(Color::Red).bg(Color::Rgb(60, 20, 20));
+
+    let mut document = RenderedDocument::empty(width);
+    for (i, line) in content.lines().enumerate() {
+        let mut spans = vec![Span::styled(
+            format!("{:>width$} │ ", i + 1, width = gutter_width),
+            gutter_style,
+        )];
+
+        let body = line.trim_end_matches(['\r']);
+        let text_end = body.trim_end_matches([' ', '\t']).len();
+        let mut column = 0;
+        let mut text = String::new();
+        for (offset, c) in body.char_indices() {
+            let trailing = offset >= text_end;
+            if c == '\t' || trailing {
+                if !text.is_empty() {
+                    spans.push(Span::raw(std::mem::take(&mut text)));
+                }
+                let style = if trailing {
+                    trailing_style
+                } else {
+                    marker_style
+                };
+                if c == '\t' {
+                    let pad = TAB_WIDTH - column % TAB_WIDTH;
+                    spans.push(Span::styled(format!("→{}", " ".repeat(pad - 1)), style));
+                    column += pad;
+                } else {
+                    spans.push(Span::styled("·", style));
+                    column += 1;
+                }
+            } else {
+                text.push(c);
+                column += 1;
+            }
+        }
+        if !text.is_empty() {
+            spans.push(Span::raw(text));
+        }
+
+        document.push_line(Line::from(spans), i);
+    }
+    document
+}
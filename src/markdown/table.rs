@@ -0,0 +1,285 @@
+use pulldown_cmark::Alignment;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use unicode_width::UnicodeWidthChar;
+
+/// Columns are never squeezed below this many cells when fitting a table.
+const MIN_COLUMN_WIDTH: usize = 3;
+
+/// A GFM table collected from parser events, drawn once it is complete.
+#[derive(Debug, Default)]
+pub struct Table {
+    alignments: Vec<Alignment>,
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    header_rows: usize,
+    in_head: bool,
+}
+
+impl Table {
+    pub fn new(alignments: Vec<Alignment>) -> Self {
+        Self {
+            alignments,
+            ..Self::default()
+        }
+    }
+
+    pub fn start_head(&mut self) {
+        self.in_head = true;
+        self.rows.push(Vec::new());
+    }
+
+    pub fn end_head(&mut self) {
+        self.in_head = false;
+        self.header_rows = self.rows.len();
+    }
+
+    pub fn start_row(&mut self) {
+        self.rows.push(Vec::new());
+    }
+
+    pub fn start_cell(&mut self) {
+        if let Some(row) = self.rows.last_mut() {
+            row.push(Vec::new());
+        }
+    }
+
+    pub fn push(&mut self, span: Span<'static>) {
+        let span = if self.in_head {
+            span.patch_style(Style::default().add_modifier(Modifier::BOLD))
+        } else {
+            span
+        };
+        if let Some(cell) = self.rows.last_mut().and_then(|row| row.last_mut()) {
+            cell.push(span);
+        }
+    }
+
+    /// Draw the table with box-drawing borders, fitting it into `max_width`
+    /// columns by shrinking the widest columns and truncating their cells.
+    pub fn render(&self, max_width: usize) -> Vec<Line<'static>> {
+        let columns = self
+            .rows
+            .iter()
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0)
+            .max(self.alignments.len());
+        if columns == 0 {
+            return Vec::new();
+        }
+
+        let mut widths = vec![1; columns];
+        for row in &self.rows {
+            for (col, cell) in row.iter().enumerate() {
+                widths[col] = widths[col].max(cell_width(cell));
+            }
+        }
+        fit_widths(&mut widths, max_width);
+
+        let border = Style::default().fg(Color::DarkGray);
+        let rule = |left: &str, mid: &str, right: &str| {
+            let segments: Vec<String> = widths.iter().map(|w| "─".repeat(w + 2)).collect();
+            Line::from(Span::styled(
+                format!("{}{}{}", left, segments.join(mid), right),
+                border,
+            ))
+        };
+
+        let mut lines = vec![rule("┌", "┬", "┐")];
+        for (row_idx, row) in self.rows.iter().enumerate() {
+            if row_idx > 0 && row_idx == self.header_rows {
+                lines.push(rule("├", "┼", "┤"));
+            }
+
+            let mut spans = vec![Span::styled("│", border)];
+            for (col, width) in widths.iter().enumerate() {
+                let cell = row.get(col).map(Vec::as_slice).unwrap_or_default();
+                let alignment = self.alignments.get(col).copied().unwrap_or(Alignment::None);
+                spans.push(Span::raw(" "));
+                spans.extend(align_cell(cell, *width, alignment));
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled("│", border));
+            }
+            lines.push(Line::from(spans));
+        }
+        lines.push(rule("└", "┴", "┘"));
+
+        // Too many columns to fit even at minimum width: clip at the pane edge
+        lines
+            .into_iter()
+            .map(|line| {
+                if line.width() > max_width {
+                    Line::from(truncate(&line.spans, max_width))
+                } else {
+                    line
+                }
+            })
+            .collect()
+    }
+}
+
+fn cell_width(cell: &[Span]) -> usize {
+    cell.iter().map(Span::width).sum()
+}
+
+/// Shrink the widest columns until borders and padding fit into `max_width`.
+fn fit_widths(widths: &mut [usize], max_width: usize) {
+    // Each column adds two spaces of padding and one border, plus the closing border
+    let chrome = widths.len() * 3 + 1;
+    let available = max_width.saturating_sub(chrome);
+    while widths.iter().sum::<usize>() > available {
+        let Some(widest) = widths
+            .iter_mut()
+            .filter(|w| **w > MIN_COLUMN_WIDTH)
+            .max_by_key(|w| **w)
+        else {
+            break;
+        };
+        *widest -= 1;
+    }
+}
+
+/// Pad (or truncate with an ellipsis) a cell to exactly `width` columns.
+fn align_cell(cell: &[Span<'static>], width: usize, alignment: Alignment) -> Vec<Span<'static>> {
+    let mut spans = if cell_width(cell) > width {
+        truncate(cell, width)
+    } else {
+        cell.to_vec()
+    };
+
+    let padding = width.saturating_sub(cell_width(&spans));
+    let (left, right) = match alignment {
+        Alignment::Right => (padding, 0),
+        Alignment::Center => (padding / 2, padding - padding / 2),
+        Alignment::Left | Alignment::None => (0, padding),
+    };
+    if left > 0 {
+        spans.insert(0, Span::raw(" ".repeat(left)));
+    }
+    if right > 0 {
+        spans.push(Span::raw(" ".repeat(right)));
+    }
+    spans
+}
+
+fn truncate(cell: &[Span<'static>], width: usize) -> Vec<Span<'static>> {
+    let limit = width.saturating_sub(1);
+    let mut used = 0;
+    let mut spans = Vec::new();
+    'spans: for span in cell {
+        let mut text = String::new();
+        for c in span.content.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w > limit {
+                if !text.is_empty() {
+                    spans.push(Span::styled(text, span.style));
+                }
+                break 'spans;
+            }
+            used += w;
+            text.push(c);
+        }
+        if !text.is_empty() {
+            spans.push(Span::styled(text, span.style));
+        }
+    }
+    spans.push(Span::styled("…", Style::default().fg(Color::DarkGray)));
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use unicode_width::UnicodeWidthStr;
+
+    /// A table with one header row and `rows` below it.
+    fn table(alignments: &[Alignment], header: &[&str], rows: &[&[&str]]) -> Table {
+        let mut table = Table::new(alignments.to_vec());
+        table.start_head();
+        for cell in header {
+            table.start_cell();
+            table.push(Span::raw(cell.to_string()));
+        }
+        table.end_head();
+        for row in rows {
+            table.start_row();
+            for cell in *row {
+                table.start_cell();
+                table.push(Span::raw(cell.to_string()));
+            }
+        }
+        table
+    }
+
+    fn texts(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| {
+                line.spans
+                    .iter()
+                    .map(|span| span.content.as_ref())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn aligns_cells() {
+        let table = table(
+            &[Alignment::Left, Alignment::Center, Alignment::Right],
+            &["left", "center", "right"],
+            &[&["a", "b", "c"], &["dd", "ee", "ff"]],
+        );
+        assert_eq!(
+            texts(&table.render(80)),
+            [
+                "┌──────┬────────┬───────┐",
+                "│ left │ center │ right │",
+                "├──────┼────────┼───────┤",
+                "│ a    │   b    │     c │",
+                "│ dd   │   ee   │    ff │",
+                "└──────┴────────┴───────┘",
+            ]
+        );
+    }
+
+    #[test]
+    fn measures_wide_characters() {
+        let table = table(
+            &[Alignment::None, Alignment::Right],
+            &["名前", "🎉"],
+            &[&["日本語", "ok"]],
+        );
+        let lines = texts(&table.render(80));
+        assert_eq!(lines[1], "│ 名前   │ 🎉 │");
+        assert_eq!(lines[3], "│ 日本語 │ ok │");
+        assert!(lines.iter().all(|line| line.width() == lines[0].width()));
+    }
+
+    #[test]
+    fn truncates_to_fit() {
+        let table = table(
+            &[],
+            &["id", "description"],
+            &[
+                &["1", "a long description that does not fit"],
+                &["2", "日本語の説明"],
+            ],
+        );
+        let lines = texts(&table.render(24));
+        assert!(lines.iter().all(|line| line.width() == 24), "{:?}", lines);
+        assert_eq!(lines[3], "│ 1  │ a long descrip… │");
+        assert_eq!(lines[4], "│ 2  │ 日本語の説明    │");
+        // A wide character that would cross the edge is dropped whole
+        let narrow = texts(&table.render(17));
+        assert_eq!(narrow[4], "│ 2  │ 日本語…  │");
+    }
+
+    #[test]
+    fn clips_when_columns_cannot_shrink() {
+        let table = table(&[], &["aaaa", "bbbb", "cccc"], &[]);
+        let lines = texts(&table.render(10));
+        assert!(lines.iter().all(|line| line.width() <= 10));
+        assert!(lines.iter().all(|line| line.ends_with('…')));
+    }
+}
@@ -0,0 +1,233 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+use regex::Regex;
+
+use crate::markdown::frontmatter::body_lines;
+
+/// What kind of work item a line holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoKind {
+    Task { checked: bool },
+    Todo,
+    Fixme,
+}
+
+impl TodoKind {
+    /// Whether the item still needs doing; `TODO` and `FIXME` markers are
+    /// always open.
+    pub fn is_open(self) -> bool {
+        !matches!(self, Self::Task { checked: true })
+    }
+}
+
+/// A task list item or `TODO`/`FIXME` marker found in a markdown source file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoItem {
+    pub kind: TodoKind,
+    pub text: String,
+    /// Zero-based source line
+    pub line: usize,
+}
+
+fn task_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"^\s*(?:>\s*)*(?:[-*+]|\d{1,9}[.)])\s+\[([ xX])\](?:\s+(.*))?$").unwrap()
+    })
+}
+
+fn marker_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\b(TODO|FIXME)\b:?").unwrap())
+}
+
+/// Extract every GFM task list item and `TODO`/`FIXME` marker, skipping
+/// fenced code blocks, inline code and leading frontmatter. A task line
+/// that also mentions `TODO` is reported once, as a task.
+pub fn extract_todos(content: &str) -> Vec<TodoItem> {
+    let mut items = Vec::new();
+    for (i, line, in_code) in body_lines(content) {
+        if in_code {
+            continue;
+        }
+        let trimmed = line.trim();
+
+        if let Some(captures) = task_pattern().captures(line) {
+            items.push(TodoItem {
+                kind: TodoKind::Task {
+                    checked: &captures[1] != " ",
+                },
+                text: captures
+                    .get(2)
+                    .map_or("", |m| m.as_str())
+                    .trim()
+                    .to_string(),
+                line: i,
+            });
+            continue;
+        }
+
+        // Markers inside `inline code` are talking about TODOs, not leaving one
+        let marker = marker_pattern()
+            .captures_iter(line)
+            .find(|c| line[..c.get(0).unwrap().start()].matches('`').count() % 2 == 0);
+        if let Some(captures) = marker {
+            let kind = if &captures[1] == "TODO" {
+                TodoKind::Todo
+            } else {
+                TodoKind::Fixme
+            };
+            let rest = line[captures.get(0).unwrap().end()..]
+                .trim()
+                .trim_end_matches("-->")
+                .trim_end();
+            let text = if rest.is_empty() { trimmed } else { rest };
+            items.push(TodoItem {
+                kind,
+                text: text.to_string(),
+                line: i,
+            });
+        }
+    }
+
+    items
+}
+
+struct CachedTodos {
+    mtime: Option<SystemTime>,
+    items: Vec<TodoItem>,
+}
+
+/// Items extracted from each file, re-read only when its modification time
+/// changes, so opening the dashboard costs one `stat` per unchanged file.
+#[derive(Default)]
+pub struct TodoCache {
+    files: HashMap<PathBuf, CachedTodos>,
+}
+
+impl TodoCache {
+    /// Forget files that are no longer scanned.
+    pub fn retain(&mut self, files: &[PathBuf]) {
+        let wanted: HashSet<&PathBuf> = files.iter().collect();
+        self.files.retain(|path, _| wanted.contains(path));
+    }
+
+    /// Items of `path`, read again if it changed since they were extracted.
+    pub fn items(&mut self, path: &Path) -> &[TodoItem] {
+        let mtime = fs::metadata(path).and_then(|m| m.modified()).ok();
+        let cached = self.files.entry(path.to_path_buf()).or_insert(CachedTodos {
+            mtime: None,
+            items: Vec::new(),
+        });
+        if cached.mtime.is_none() || cached.mtime != mtime {
+            cached.items = fs::read_to_string(path)
+                .map(|content| extract_todos(&content))
+                .unwrap_or_default();
+            cached.mtime = mtime;
+        }
+        &cached.items
+    }
+}
+
+/// How the TODO dashboard groups its items.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoGroup {
+    File,
+    Directory,
+    /// By the `owner` field of each file's frontmatter
+    Owner,
+}
+
+impl TodoGroup {
+    pub fn next(self) -> Self {
+        match self {
+            TodoGroup::File => TodoGroup::Directory,
+            TodoGroup::Directory => TodoGroup::Owner,
+            TodoGroup::Owner => TodoGroup::File,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TodoGroup::File => "file",
+            TodoGroup::Directory => "directory",
+            TodoGroup::Owner => "owner",
+        }
+    }
+}
+
+/// Which items the TODO dashboard shows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TodoFilter {
+    Open,
+    Done,
+    All,
+}
+
+impl TodoFilter {
+    pub fn next(self) -> Self {
+        match self {
+            TodoFilter::Open => TodoFilter::Done,
+            TodoFilter::Done => TodoFilter::All,
+            TodoFilter::All => TodoFilter::Open,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            TodoFilter::Open => "open",
+            TodoFilter::Done => "done",
+            TodoFilter::All => "all",
+        }
+    }
+
+    pub fn matches(self, kind: TodoKind) -> bool {
+        match self {
+            TodoFilter::Open => kind.is_open(),
+            TodoFilter::Done => !kind.is_open(),
+            TodoFilter::All => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(content: &str) -> Vec<usize> {
+        extract_todos(content)
+            .iter()
+            .map(|item| item.line)
+            .collect()
+    }
+
+    #[test]
+    fn finds_tasks_and_markers() {
+        let items = extract_todos("- [ ] open\n- [x] done\nTODO: write more\n");
+        let kinds: Vec<_> = items.iter().map(|item| item.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                TodoKind::Task { checked: false },
+                TodoKind::Task { checked: true },
+                TodoKind::Todo
+            ]
+        );
+        assert_eq!(items[2].text, "write more");
+    }
+
+    #[test]
+    fn skips_frontmatter_and_code() {
+        let content = "---\nnote: TODO\n---\n````\n```\n- [ ] in code\n````\nFIXME here\n";
+        assert_eq!(lines(content), [7]);
+    }
+
+    #[test]
+    fn skips_markers_in_inline_code() {
+        assert!(lines("Write `TODO` to leave a note").is_empty());
+    }
+}
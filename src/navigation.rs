@@ -0,0 +1,100 @@
+use std::path::PathBuf;
+
+/// History is trimmed to this many entries in each direction.
+const MAX_ENTRIES: usize = 100;
+
+/// A place in the preview: which file and how far it was scrolled.
+///
+/// Entries store paths rather than tree indices so they stay valid when the
+/// tree is filtered, folded or rescanned.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NavEntry {
+    pub path: PathBuf,
+    pub scroll: u16,
+}
+
+/// Back/forward stacks for link follows and jumps, like a browser or vim's jumplist.
+#[derive(Debug, Clone, Default)]
+pub struct NavHistory {
+    back: Vec<NavEntry>,
+    forward: Vec<NavEntry>,
+}
+
+impl NavHistory {
+    /// Remember `from` before jumping somewhere new. Clears the forward stack.
+    pub fn record(&mut self, from: NavEntry) {
+        if self.back.last() != Some(&from) {
+            self.back.push(from);
+            if self.back.len() > MAX_ENTRIES {
+                self.back.remove(0);
+            }
+        }
+        self.forward.clear();
+    }
+
+    /// Step back, leaving `current` to return to with [`NavHistory::forward`].
+    pub fn back(&mut self, current: NavEntry) -> Option<NavEntry> {
+        let entry = self.back.pop()?;
+        self.forward.push(current);
+        Some(entry)
+    }
+
+    pub fn forward(&mut self, current: NavEntry) -> Option<NavEntry> {
+        let entry = self.forward.pop()?;
+        self.back.push(current);
+        Some(entry)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(name: &str) -> NavEntry {
+        NavEntry {
+            path: PathBuf::from(name),
+            scroll: 0,
+        }
+    }
+
+    #[test]
+    fn steps_back_and_forward() {
+        let mut history = NavHistory::default();
+        history.record(at("a"));
+        history.record(at("b"));
+        assert_eq!(history.back(at("c")), Some(at("b")));
+        assert_eq!(history.back(at("b")), Some(at("a")));
+        assert_eq!(history.back(at("a")), None);
+        assert_eq!(history.forward(at("a")), Some(at("b")));
+        assert_eq!(history.forward(at("b")), Some(at("c")));
+        assert_eq!(history.forward(at("c")), None);
+    }
+
+    #[test]
+    fn recording_clears_forward_and_skips_repeats() {
+        let mut history = NavHistory::default();
+        history.record(at("a"));
+        history.record(at("a"));
+        assert_eq!(history.back(at("b")), Some(at("a")));
+        history.record(at("a"));
+        assert_eq!(history.forward(at("c")), None);
+        assert_eq!(history.back(at("c")), Some(at("a")));
+        assert_eq!(history.back(at("a")), None);
+    }
+
+    #[test]
+    fn drops_the_oldest_entries() {
+        let mut history = NavHistory::default();
+        for i in 0..MAX_ENTRIES + 5 {
+            history.record(at(&i.to_string()));
+        }
+        let mut current = at("now");
+        let mut steps = Vec::new();
+        while let Some(entry) = history.back(current.clone()) {
+            steps.push(entry.clone());
+            current = entry;
+        }
+        assert_eq!(steps.len(), MAX_ENTRIES);
+        assert_eq!(steps.last(), Some(&at("5")));
+    }
+}
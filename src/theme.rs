@@ -0,0 +1,56 @@
+use std::sync::OnceLock;
+
+use ratatui::style::{Color, Style};
+
+/// Environment variable choosing the theme, `dark` or `light`.
+const THEME_VAR: &str = "MD_EXPLORER_THEME";
+
+/// Whether the app is drawn for a dark or a light terminal background.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+impl Theme {
+    /// The theme for this session, detected on first use.
+    pub fn current() -> Self {
+        static THEME: OnceLock<Theme> = OnceLock::new();
+        *THEME.get_or_init(Self::detect)
+    }
+
+    /// Inline code, and math that could not be converted: code coloured text
+    /// on a background a shade off the terminal's.
+    pub fn code(self) -> Style {
+        match self {
+            Theme::Dark => Style::default().fg(Color::Green).bg(Color::Rgb(30, 30, 30)),
+            Theme::Light => Style::default()
+                .fg(Color::Rgb(0, 110, 40))
+                .bg(Color::Rgb(235, 235, 235)),
+        }
+    }
+
+    /// `MD_EXPLORER_THEME` if set, otherwise the background color terminals
+    /// report in `COLORFGBG` (such as `0;15` for black on white), falling
+    /// back to dark.
+    fn detect() -> Self {
+        match std::env::var(THEME_VAR)
+            .unwrap_or_default()
+            .to_lowercase()
+            .as_str()
+        {
+            "dark" => return Theme::Dark,
+            "light" => return Theme::Light,
+            _ => {}
+        }
+
+        let background = std::env::var("COLORFGBG")
+            .ok()
+            .and_then(|value| value.rsplit(';').next()?.parse::<u8>().ok());
+        match background {
+            // White and light gray in the 16-color palette
+            Some(7 | 15) => Theme::Light,
+            _ => Theme::Dark,
+        }
+    }
+}
@@ -0,0 +1,61 @@
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+use crate::app::{App, Focus};
+use crate::ui::display_path;
+
+pub fn render_backlinks(frame: &mut Frame, app: &App, area: Rect) {
+    let border_color = if app.focus == Focus::Backlinks {
+        Color::Cyan
+    } else {
+        Color::DarkGray
+    };
+
+    let backlinks = app.selected_backlinks();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border_color))
+        .title(format!(" Backlinks ({}) ", backlinks.len()));
+
+    if backlinks.is_empty() {
+        let message = if app.backlinks_loading() {
+            "Finding links…"
+        } else {
+            "No documents link here"
+        };
+        let empty = Paragraph::new(Line::from(Span::styled(
+            message,
+            Style::default().fg(Color::DarkGray),
+        )))
+        .block(block);
+        frame.render_widget(empty, area);
+        return;
+    }
+
+    let items: Vec<ListItem> = backlinks
+        .iter()
+        .map(|backlink| {
+            let location = format!("{}:{}", display_path(&backlink.source), backlink.line + 1);
+            ListItem::new(Line::from(vec![
+                Span::styled(location, Style::default().fg(Color::Cyan)),
+                Span::raw("  "),
+                Span::styled(backlink.snippet.clone(), Style::default().fg(Color::White)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).block(block).highlight_style(
+        Style::default()
+            .bg(Color::Rgb(40, 40, 60))
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let mut state = ListState::default();
+    if app.focus == Focus::Backlinks {
+        state.select(Some(app.backlinks_selected.min(backlinks.len() - 1)));
+    }
+    frame.render_stateful_widget(list, area, &mut state);
+}
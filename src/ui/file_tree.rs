@@ -7,6 +7,7 @@ use ratatui::widgets::{Block, Borders, List, ListItem, ListState};
 use ratatui::Frame;
 
 use crate::app::{App, Focus};
+use crate::fs::scanner::SortOrder;
 
 pub fn render_file_tree(frame: &mut Frame, app: &mut App, area: Rect) {
     // Update tree height for scroll calculations (subtract 2 for borders)
@@ -20,7 +21,10 @@ pub fn render_file_tree(frame: &mut Frame, app: &mut App, area: Rect) {
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(border_color))
-        .title(" Files ");
+        .title(match app.file_tree.sort_order {
+            SortOrder::Name => " Files ".to_string(),
+            order => format!(" Files (by {}) ", order.label()),
+        });
 
     let flat_list = app.file_tree.flat_list();
     let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("~"));
@@ -84,10 +88,17 @@ pub fn render_file_tree(frame: &mut Frame, app: &mut App, area: Rect) {
                 Style::default().fg(Color::White)
             };
 
-            let line = Line::from(vec![
+            let mut spans = vec![
                 Span::raw(format!("{}{}", indent, prefix)),
                 Span::styled(format!("{}{}", icon, display_name), style),
-            ]);
+            ];
+            if let Some(score) = app.content_scores.get(path) {
+                spans.push(Span::styled(
+                    format!("  {:.1}", score),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+            let line = Line::from(spans);
 
             ListItem::new(line)
         })
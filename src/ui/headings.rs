@@ -0,0 +1,89 @@
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+use crate::app::App;
+use crate::ui::display_path;
+
+pub fn render_heading_picker(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+
+    // Center the popup
+    let popup_width = (area.width * 4 / 5)
+        .max(40)
+        .min(area.width.saturating_sub(4));
+    let popup_height = (area.height * 7 / 10)
+        .max(10)
+        .min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    frame.render_widget(Clear, popup_area);
+
+    let entries = app.heading_index.as_deref().unwrap_or_default();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(if app.heading_index_loading.is_some() {
+            " Go to Heading (indexing…) ".to_string()
+        } else {
+            format!(
+                " Go to Heading ({}/{}) ",
+                app.heading_matches.len(),
+                entries.len()
+            )
+        });
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let input = Paragraph::new(Line::from(vec![
+        Span::styled("# ", Style::default().fg(Color::Cyan)),
+        Span::styled(app.heading_query.clone(), Style::default().fg(Color::White)),
+        Span::styled("█", Style::default().fg(Color::Cyan)),
+    ]));
+    frame.render_widget(input, Rect::new(inner.x, inner.y, inner.width, 1));
+
+    let list_area = Rect::new(
+        inner.x,
+        inner.y + 2,
+        inner.width,
+        inner.height.saturating_sub(2),
+    );
+
+    let items: Vec<ListItem> = app
+        .heading_matches
+        .iter()
+        .map(|&idx| {
+            let entry = &entries[idx];
+            let indent = "  ".repeat(entry.heading.level.saturating_sub(1) as usize);
+            let location = format!("  {}:{}", display_path(&entry.path), entry.heading.line + 1);
+            ListItem::new(Line::from(vec![
+                Span::raw(indent),
+                Span::styled(
+                    format!("{} ", "#".repeat(entry.heading.level as usize)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    entry.heading.text.clone(),
+                    Style::default().fg(Color::White),
+                ),
+                Span::styled(location, Style::default().fg(Color::DarkGray)),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).highlight_style(
+        Style::default()
+            .bg(Color::Rgb(40, 40, 60))
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let mut state = ListState::default();
+    if !app.heading_matches.is_empty() {
+        state.select(Some(app.heading_selected));
+    }
+    frame.render_stateful_widget(list, list_area, &mut state);
+}
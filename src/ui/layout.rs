@@ -1,15 +1,26 @@
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
-use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
 use ratatui::Frame;
 
 use crate::app::{App, Focus, Mode};
+use crate::ui::backlinks::render_backlinks;
 use crate::ui::file_tree::render_file_tree;
+use crate::ui::headings::render_heading_picker;
+use crate::ui::outline::render_outline;
 use crate::ui::preview::render_preview;
 use crate::ui::search::render_search_bar;
+use crate::ui::slides::render_slides;
+use crate::ui::todos::render_todos;
 
 pub fn render(frame: &mut Frame, app: &mut App) {
+    // Slides take over the whole screen
+    if app.mode == Mode::Slides {
+        render_slides(frame, app);
+        return;
+    }
+
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
@@ -23,8 +34,16 @@ pub fn render(frame: &mut Frame, app: &mut App) {
     render_main_content(frame, app, chunks[1]);
     render_status_bar(frame, app, chunks[2]);
 
+    if app.mode == Mode::HeadingSearch {
+        render_heading_picker(frame, app);
+    }
+
+    if app.mode == Mode::Todos {
+        render_todos(frame, app);
+    }
+
     if app.mode == Mode::Help {
-        render_help_popup(frame);
+        render_help_popup(frame, app);
     }
 }
 
@@ -38,7 +57,31 @@ fn render_main_content(frame: &mut Frame, app: &mut App, area: Rect) {
         .split(area);
 
     render_file_tree(frame, app, chunks[0]);
-    render_preview(frame, app, chunks[1]);
+
+    let document_area = if app.show_backlinks {
+        let count = app.selected_backlinks().len().max(1) as u16;
+        let height = (count + 2).min(chunks[1].height / 3).max(3);
+        let right = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(5), Constraint::Length(height)])
+            .split(chunks[1]);
+        render_backlinks(frame, app, right[1]);
+        right[0]
+    } else {
+        chunks[1]
+    };
+
+    if app.show_outline {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(20), Constraint::Percentage(30)])
+            .split(document_area);
+        // The preview fills in the outline rows, so it renders first
+        render_preview(frame, app, columns[0]);
+        render_outline(frame, app, columns[1]);
+    } else {
+        render_preview(frame, app, document_area);
+    }
 }
 
 fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
@@ -46,9 +89,11 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         Focus::Tree => "[Tree]",
         Focus::Preview => "[Preview]",
         Focus::Search => "[Search]",
+        Focus::Backlinks => "[Backlinks]",
+        Focus::Outline => "[Outline]",
     };
 
-    let help_text = vec![
+    let mut help_text = vec![
         Span::styled("↑↓/jk", Style::default().fg(Color::Cyan)),
         Span::raw(" Nav  "),
         Span::styled("Tab", Style::default().fg(Color::Cyan)),
@@ -68,28 +113,39 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
         Span::styled(focus_indicator, Style::default().fg(Color::Yellow)),
     ];
 
+    if !app.preview_query.is_empty() {
+        let counter = if app.preview_match_rows.is_empty() {
+            format!("  /{}: no matches", app.preview_query)
+        } else {
+            format!(
+                "  /{}: {}/{}",
+                app.preview_query,
+                app.preview_match_current + 1,
+                app.preview_match_rows.len()
+            )
+        };
+        help_text.push(Span::styled(counter, Style::default().fg(Color::Magenta)));
+    }
+
+    // A status message takes the place of the key hints until the next key press
+    if let Some(message) = &app.status_message {
+        help_text = vec![
+            Span::styled(message.clone(), Style::default().fg(Color::Yellow)),
+            Span::raw("  "),
+            Span::styled(focus_indicator, Style::default().fg(Color::Yellow)),
+        ];
+    }
+
     let status =
         Paragraph::new(Line::from(help_text)).block(Block::default().borders(Borders::ALL));
 
     frame.render_widget(status, area);
 }
 
-fn render_help_popup(frame: &mut Frame) {
+fn render_help_popup(frame: &mut Frame, app: &mut App) {
     let area = frame.area();
 
-    // Center the popup
-    let popup_width = 60.min(area.width.saturating_sub(4));
-    let popup_height = 21.min(area.height.saturating_sub(4));
-    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
-    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
-
-    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
-
-    // Clear the area behind the popup
-    let clear = Block::default().style(Style::default().bg(Color::Black));
-    frame.render_widget(clear, popup_area);
-
-    let help_text = vec![
+    let help_lines = vec![
         Line::from(Span::styled(
             "MD Explorer - Help",
             Style::default()
@@ -117,6 +173,10 @@ fn render_help_popup(frame: &mut Frame) {
             Span::styled("c        ", Style::default().fg(Color::Yellow)),
             Span::raw("Toggle CLAUDE.md only"),
         ]),
+        Line::from(vec![
+            Span::styled("s        ", Style::default().fg(Color::Yellow)),
+            Span::raw("Sort by name/title/date"),
+        ]),
         Line::from(vec![
             Span::styled("Enter    ", Style::default().fg(Color::Yellow)),
             Span::raw("Open file in $EDITOR"),
@@ -127,7 +187,63 @@ fn render_help_popup(frame: &mut Frame) {
         ]),
         Line::from(vec![
             Span::styled("/        ", Style::default().fg(Color::Yellow)),
-            Span::raw("Start search/filter"),
+            Span::raw("Start search/filter (in preview: find)"),
+        ]),
+        Line::from(vec![
+            Span::styled("n/N      ", Style::default().fg(Color::Yellow)),
+            Span::raw("Next/previous match in preview"),
+        ]),
+        Line::from(vec![
+            Span::styled("Tab      ", Style::default().fg(Color::Yellow)),
+            Span::raw("Cycle fuzzy/exact/regex/content"),
+        ]),
+        Line::from(vec![
+            Span::styled("↑↓/^R    ", Style::default().fg(Color::Yellow)),
+            Span::raw("Search history / reverse search"),
+        ]),
+        Line::from(vec![
+            Span::styled("0-9      ", Style::default().fg(Color::Yellow)),
+            Span::raw("Follow link [n] (in preview)"),
+        ]),
+        Line::from(vec![
+            Span::styled("^O/^I    ", Style::default().fg(Color::Yellow)),
+            Span::raw("Jump back/forward (also Alt-←/→)"),
+        ]),
+        Line::from(vec![
+            Span::styled("b        ", Style::default().fg(Color::Yellow)),
+            Span::raw("Backlinks panel"),
+        ]),
+        Line::from(vec![
+            Span::styled("o        ", Style::default().fg(Color::Yellow)),
+            Span::raw("Outline panel"),
+        ]),
+        Line::from(vec![
+            Span::styled("v        ", Style::default().fg(Color::Yellow)),
+            Span::raw("Rendered/raw source view"),
+        ]),
+        Line::from(vec![
+            Span::styled("i        ", Style::default().fg(Color::Yellow)),
+            Span::raw("Images/alt text"),
+        ]),
+        Line::from(vec![
+            Span::styled("[/]      ", Style::default().fg(Color::Yellow)),
+            Span::raw("Previous/next task (in preview)"),
+        ]),
+        Line::from(vec![
+            Span::styled("x        ", Style::default().fg(Color::Yellow)),
+            Span::raw("Check/uncheck task (in preview)"),
+        ]),
+        Line::from(vec![
+            Span::styled("#        ", Style::default().fg(Color::Yellow)),
+            Span::raw("Go to heading in any file"),
+        ]),
+        Line::from(vec![
+            Span::styled("t        ", Style::default().fg(Color::Yellow)),
+            Span::raw("TODO dashboard"),
+        ]),
+        Line::from(vec![
+            Span::styled("p        ", Style::default().fg(Color::Yellow)),
+            Span::raw("Present as slides"),
         ]),
         Line::from(vec![
             Span::styled("Esc      ", Style::default().fg(Color::Yellow)),
@@ -147,17 +263,46 @@ fn render_help_popup(frame: &mut Frame) {
         ]),
         Line::from(""),
         Line::from(Span::styled(
-            "Press any key to close",
+            "↑↓/jk scroll, any other key closes",
             Style::default().fg(Color::DarkGray),
         )),
     ];
 
-    let help_paragraph = Paragraph::new(help_text).block(
-        Block::default()
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(Color::Cyan))
-            .title(" Help "),
-    );
+    // Center the popup
+    let popup_width = 60.min(area.width.saturating_sub(4));
+    let popup_height = (help_lines.len() as u16 + 2).min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    // Clear the area behind the popup
+    frame.render_widget(Clear, popup_area);
+    let background = Block::default().style(Style::default().bg(Color::Black));
+    frame.render_widget(background, popup_area);
+
+    // Short terminals scroll the help instead of cutting it off
+    let visible = popup_height.saturating_sub(2) as usize;
+    let hidden = help_lines.len().saturating_sub(visible);
+    app.help_scroll = app.help_scroll.min(hidden as u16);
+    let title = if hidden > 0 {
+        format!(
+            " Help ({}-{} of {}, ↑↓ scroll) ",
+            app.help_scroll + 1,
+            app.help_scroll as usize + visible,
+            help_lines.len()
+        )
+    } else {
+        " Help ".to_string()
+    };
+    let help_paragraph = Paragraph::new(help_lines)
+        .scroll((app.help_scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan))
+                .title(title),
+        );
 
     frame.render_widget(help_paragraph, popup_area);
 }
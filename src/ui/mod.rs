@@ -1,4 +1,23 @@
+pub mod backlinks;
 pub mod file_tree;
+pub mod headings;
 pub mod layout;
+pub mod outline;
 pub mod preview;
 pub mod search;
+pub mod slides;
+pub mod todos;
+
+use std::path::Path;
+
+/// `path` as `~/...` when it is under the home directory, or as given.
+pub fn display_path(path: &Path) -> String {
+    let home = dirs::home_dir();
+    match home
+        .as_deref()
+        .and_then(|home| path.strip_prefix(home).ok())
+    {
+        Some(relative) => format!("~/{}", relative.display()),
+        None => path.display().to_string(),
+    }
+}
@@ -0,0 +1,71 @@
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+use crate::app::{App, Focus};
+
+pub fn render_outline(frame: &mut Frame, app: &App, area: Rect) {
+    let border_color = if app.focus == Focus::Outline {
+        Color::Cyan
+    } else {
+        Color::DarkGray
+    };
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(border_color))
+        .title(" Outline ");
+
+    if app.outline.is_empty() {
+        let empty = Paragraph::new(Line::from(Span::styled(
+            "No headings",
+            Style::default().fg(Color::DarkGray),
+        )))
+        .block(block);
+        frame.render_widget(empty, area);
+        return;
+    }
+
+    let current = app.current_section();
+    let items: Vec<ListItem> = app
+        .outline
+        .iter()
+        .enumerate()
+        .map(|(idx, entry)| {
+            let indent = "  ".repeat(entry.level.saturating_sub(1) as usize);
+            let (marker, style) = if Some(idx) == current {
+                (
+                    "▸ ",
+                    Style::default()
+                        .fg(Color::Yellow)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ("  ", Style::default().fg(Color::White))
+            };
+            ListItem::new(Line::from(vec![
+                Span::styled(marker, Style::default().fg(Color::Yellow)),
+                Span::raw(indent),
+                Span::styled(entry.text.clone(), style),
+            ]))
+        })
+        .collect();
+
+    // Only the focused outline shows a cursor; otherwise the list just keeps
+    // the current section in view
+    let (selected, highlight) = if app.focus == Focus::Outline {
+        (
+            app.outline_cursor(),
+            Style::default().bg(Color::Rgb(40, 40, 60)),
+        )
+    } else {
+        (current, Style::default())
+    };
+    let list = List::new(items).block(block).highlight_style(highlight);
+
+    let mut state = ListState::default();
+    state.select(selected);
+    frame.render_stateful_widget(list, area, &mut state);
+}
@@ -1,36 +1,52 @@
-use std::fs;
+use std::sync::Arc;
 
 use ratatui::layout::Rect;
-use ratatui::style::{Color, Modifier, Style};
+use ratatui::style::{Color, Style};
 use ratatui::text::{Line, Span};
 use ratatui::widgets::{Block, Borders, Paragraph, Wrap};
 use ratatui::Frame;
 
-use crate::app::{App, Focus};
+use crate::app::{App, Focus, ImagePlacement, Mode, OutlineEntry, PreviewTask};
+use crate::markdown::render::RenderedDocument;
 
-pub fn render_preview(frame: &mut Frame, app: &App, area: Rect) {
+pub fn render_preview(frame: &mut Frame, app: &mut App, area: Rect) {
     let border_color = if app.focus == Focus::Preview {
         Color::Cyan
     } else {
         Color::DarkGray
     };
+    let width = area.width.saturating_sub(2);
+    let height = area.height.saturating_sub(2) as usize;
 
-    let selected_file = app.selected_file();
+    let selected_file = app.selected_file().cloned();
+    let mut loading = false;
 
-    let (title, content) = match selected_file {
+    let (title, document) = match &selected_file {
         Some(path) if path.is_file() => {
             let filename = path
                 .file_name()
                 .map(|n| n.to_string_lossy().to_string())
                 .unwrap_or_else(|| "Unknown".to_string());
 
-            match fs::read_to_string(path) {
-                Ok(content) => (format!(" {} ", filename), render_markdown(&content)),
-                Err(e) => (
-                    format!(" {} ", filename),
-                    vec![Line::from(format!("Error reading file: {}", e))],
-                ),
-            }
+            let document = app.preview_document(path, width).unwrap_or_else(|| {
+                loading = true;
+                Arc::new(RenderedDocument::placeholder(
+                    vec![
+                        Line::from(""),
+                        Line::from(Span::styled(
+                            "  Loading…",
+                            Style::default().fg(Color::DarkGray),
+                        )),
+                    ],
+                    width,
+                ))
+            });
+            let title = if app.raw_view {
+                format!(" {} [raw] ", filename)
+            } else {
+                format!(" {} ", filename)
+            };
+            (title, document)
         }
         Some(path) if path.is_dir() => {
             let dirname = path
@@ -39,38 +55,141 @@ pub fn render_preview(frame: &mut Frame, app: &App, area: Rect) {
                 .unwrap_or_else(|| "Directory".to_string());
             (
                 format!(" {} ", dirname),
+                Arc::new(RenderedDocument::placeholder(
+                    vec![
+                        Line::from(""),
+                        Line::from(Span::styled(
+                            "  Directory",
+                            Style::default().fg(Color::DarkGray),
+                        )),
+                        Line::from(""),
+                        Line::from(Span::styled(
+                            "  Select a markdown file to preview",
+                            Style::default().fg(Color::DarkGray),
+                        )),
+                    ],
+                    width,
+                )),
+            )
+        }
+        _ => (
+            " Preview ".to_string(),
+            Arc::new(RenderedDocument::placeholder(
                 vec![
                     Line::from(""),
                     Line::from(Span::styled(
-                        "  Directory",
-                        Style::default().fg(Color::DarkGray),
-                    )),
-                    Line::from(""),
-                    Line::from(Span::styled(
-                        "  Select a markdown file to preview",
+                        "  No file selected",
                         Style::default().fg(Color::DarkGray),
                     )),
                 ],
-            )
-        }
-        _ => (
-            " Preview ".to_string(),
-            vec![
-                Line::from(""),
-                Line::from(Span::styled(
-                    "  No file selected",
-                    Style::default().fg(Color::DarkGray),
-                )),
-            ],
+                width,
+            )),
         ),
     };
 
+    let row = |line: usize| document.row_offsets[line].min(u16::MAX as usize) as u16;
+
+    // Keep the jump target until the document has loaded
+    if !loading {
+        if let Some(line) = app
+            .preview_target_line
+            .take()
+            .and_then(|target| document.line_for_source(target))
+        {
+            app.preview_scroll = row(line);
+        }
+    }
+    if app.show_outline {
+        app.outline = document
+            .headings
+            .iter()
+            .map(|heading| OutlineEntry {
+                level: heading.level,
+                row: row(heading.line),
+                text: heading.text.clone(),
+            })
+            .collect();
+    }
+    app.preview_links = document.links.clone();
+    app.preview_tasks = document
+        .tasks
+        .iter()
+        .map(|task| PreviewTask {
+            row: row(task.line),
+            offset: task.offset,
+            checked: task.checked,
+        })
+        .collect();
+    app.preview_height = height;
+    let task_line = app.task_cursor().map(|idx| document.tasks[idx].line);
+
+    let matches = if app.preview_query.is_empty() {
+        Vec::new()
+    } else {
+        find_matches(&document.lines, &app.preview_query)
+    };
+    app.preview_match_rows = matches.iter().map(|&(line, _, _)| row(line)).collect();
+    if app.preview_match_current >= app.preview_match_rows.len() {
+        app.preview_match_current = 0;
+    }
+
+    // Only the lines in view are handed to the paragraph, so long documents
+    // cost no more to draw than short ones
+    let scroll = app.preview_scroll as usize;
+    let first = document.line_at_row(scroll);
+    let skip = scroll.saturating_sub(document.row_offsets.get(first).copied().unwrap_or(0));
+    if !loading {
+        if let Some(&source_line) = document.source_lines.get(first) {
+            app.preview_source_line = source_line;
+        }
+    }
+    let visible: Vec<Line> = (first..document.lines.len())
+        .take_while(|&idx| document.row_offsets[idx] < scroll + height)
+        .map(|idx| {
+            let ranges: Vec<(usize, usize, bool)> = matches
+                .iter()
+                .enumerate()
+                .filter(|(_, (line, _, _))| *line == idx)
+                .map(|(i, &(_, start, end))| (start, end, i == app.preview_match_current))
+                .collect();
+            let mut line = document.lines[idx].clone();
+            if !ranges.is_empty() {
+                line = split_spans(line, &ranges);
+            }
+            if task_line == Some(idx) {
+                line = line.patch_style(Style::default().bg(Color::DarkGray));
+            }
+            line
+        })
+        .collect();
+
+    // Graphics protocol images are drawn after the frame, only when wholly
+    // in view and not under a popup
+    let popup = matches!(app.mode, Mode::Help | Mode::HeadingSearch | Mode::Todos);
+    app.image_placements = document
+        .images
+        .iter()
+        .filter(|_| !loading && !popup)
+        .filter_map(|image| {
+            let top = document.row_offsets[image.line].checked_sub(scroll)?;
+            (top + image.graphics.rows as usize <= height).then(|| ImagePlacement {
+                x: area.x + 1 + image.column,
+                y: area.y + 1 + top as u16,
+                graphics: Arc::clone(&image.graphics),
+            })
+        })
+        .collect();
+
     let block = Block::default()
         .borders(Borders::ALL)
         .border_style(Style::default().fg(border_color))
         .title(title);
 
-    let scroll_indicator = if app.focus == Focus::Preview {
+    let scroll_indicator = if app.mode == Mode::PreviewSearch {
+        format!(" /{}█ ", app.preview_query)
+    } else if !app.link_hint_input.is_empty() {
+        format!(" Follow link [{}]█ ", app.link_hint_input)
+    } else if app.focus == Focus::Preview {
         format!(" [Scroll: {}] ", app.preview_scroll)
     } else {
         String::new()
@@ -78,203 +197,180 @@ pub fn render_preview(frame: &mut Frame, app: &App, area: Rect) {
 
     let block = block.title_bottom(scroll_indicator);
 
-    let paragraph = Paragraph::new(content)
+    let paragraph = Paragraph::new(visible)
         .block(block)
         .wrap(Wrap { trim: false })
-        .scroll((app.preview_scroll, 0));
+        .scroll((skip.min(u16::MAX as usize) as u16, 0));
 
     frame.render_widget(paragraph, area);
 }
 
-fn render_markdown(content: &str) -> Vec<Line<'static>> {
-    let mut lines: Vec<Line<'static>> = Vec::new();
-    let mut in_code_block = false;
-
-    for line in content.lines() {
-        if line.starts_with("```") {
-            in_code_block = !in_code_block;
-            lines.push(Line::from(Span::styled(
-                line.to_string(),
-                Style::default().fg(Color::DarkGray),
-            )));
-            continue;
+/// Every occurrence of `query` in the rendered lines, as
+/// `(line, start char, end char)` in document order. Matching is
+/// case-insensitive unless the query contains an uppercase letter.
+fn find_matches(lines: &[Line], query: &str) -> Vec<(usize, usize, usize)> {
+    let case_sensitive = query.chars().any(|c| c.is_uppercase());
+    // Folded chars with the index of the char they came from; lowercasing
+    // can turn one char into several, as with 'İ'
+    let fold = |chars: &mut dyn Iterator<Item = char>| {
+        let mut folded = Vec::new();
+        for (idx, c) in chars.enumerate() {
+            if case_sensitive {
+                folded.push((c, idx));
+            } else {
+                folded.extend(c.to_lowercase().map(|lower| (lower, idx)));
+            }
         }
+        folded
+    };
+    let needle: Vec<char> = fold(&mut query.chars())
+        .into_iter()
+        .map(|(c, _)| c)
+        .collect();
+    if needle.is_empty() {
+        return Vec::new();
+    }
 
-        if in_code_block {
-            lines.push(Line::from(Span::styled(
-                format!("  {}", line),
-                Style::default().fg(Color::Green),
-            )));
-            continue;
-        }
+    let mut matches = Vec::new();
+    for (line_idx, line) in lines.iter().enumerate() {
+        let text = fold(&mut line.spans.iter().flat_map(|span| span.content.chars()));
 
-        // Headers
-        if line.starts_with("# ") {
-            lines.push(Line::from(Span::styled(
-                line.to_string(),
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            )));
-        } else if line.starts_with("## ") {
-            lines.push(Line::from(Span::styled(
-                line.to_string(),
-                Style::default()
-                    .fg(Color::Blue)
-                    .add_modifier(Modifier::BOLD),
-            )));
-        } else if line.starts_with("### ") {
-            lines.push(Line::from(Span::styled(
-                line.to_string(),
-                Style::default()
-                    .fg(Color::Magenta)
-                    .add_modifier(Modifier::BOLD),
-            )));
-        } else if line.starts_with("#### ")
-            || line.starts_with("##### ")
-            || line.starts_with("###### ")
-        {
-            lines.push(Line::from(Span::styled(
-                line.to_string(),
-                Style::default()
-                    .fg(Color::Yellow)
-                    .add_modifier(Modifier::BOLD),
-            )));
-        }
-        // Lists
-        else if line.trim_start().starts_with("- ") || line.trim_start().starts_with("* ") {
-            let indent = line.len() - line.trim_start().len();
-            let spaces = " ".repeat(indent);
-            let rest = line
-                .trim_start()
-                .strip_prefix("- ")
-                .or_else(|| line.trim_start().strip_prefix("* "))
-                .unwrap_or(line);
-            lines.push(Line::from(vec![
-                Span::raw(spaces),
-                Span::styled("• ", Style::default().fg(Color::Cyan)),
-                Span::raw(rest.to_string()),
-            ]));
-        }
-        // Numbered lists
-        else if line
-            .trim_start()
-            .chars()
-            .next()
-            .map(|c| c.is_ascii_digit())
-            .unwrap_or(false)
-            && line.trim_start().contains(". ")
-        {
-            lines.push(Line::from(Span::styled(
-                line.to_string(),
-                Style::default().fg(Color::White),
-            )));
-        }
-        // Blockquotes
-        else if line.starts_with("> ") {
-            lines.push(Line::from(Span::styled(
-                line.to_string(),
-                Style::default()
-                    .fg(Color::DarkGray)
-                    .add_modifier(Modifier::ITALIC),
-            )));
+        // Non-overlapping matches in this line, as ranges of the line's chars
+        let mut i = 0;
+        while i + needle.len() <= text.len() {
+            let window = &text[i..i + needle.len()];
+            if window.iter().map(|(c, _)| c).eq(needle.iter()) {
+                matches.push((line_idx, window[0].1, window[needle.len() - 1].1 + 1));
+                i += needle.len();
+            } else {
+                i += 1;
+            }
         }
-        // Horizontal rules
-        else if line.trim() == "---" || line.trim() == "***" || line.trim() == "___" {
-            lines.push(Line::from(Span::styled(
-                "─".repeat(40),
-                Style::default().fg(Color::DarkGray),
-            )));
+    }
+    matches
+}
+
+/// Rebuild a line's spans so that each char range gets the match style.
+fn split_spans(line: Line<'static>, ranges: &[(usize, usize, bool)]) -> Line<'static> {
+    let match_style = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let current_style = Style::default().fg(Color::Black).bg(Color::LightRed);
+
+    let mut spans: Vec<Span<'static>> = Vec::new();
+    let mut pos = 0;
+    for span in line.spans {
+        let mut chunk = String::new();
+        let mut chunk_style = None;
+        for c in span.content.chars() {
+            let style = ranges
+                .iter()
+                .find(|(start, end, _)| pos >= *start && pos < *end)
+                .map(|(_, _, is_current)| {
+                    span.style.patch(if *is_current {
+                        current_style
+                    } else {
+                        match_style
+                    })
+                })
+                .unwrap_or(span.style);
+            if chunk_style != Some(style) && !chunk.is_empty() {
+                spans.push(Span::styled(
+                    std::mem::take(&mut chunk),
+                    chunk_style.unwrap(),
+                ));
+            }
+            chunk_style = Some(style);
+            chunk.push(c);
+            pos += 1;
         }
-        // Regular text with inline formatting
-        else {
-            lines.push(render_inline_markdown(line));
+        if let Some(style) = chunk_style.filter(|_| !chunk.is_empty()) {
+            spans.push(Span::styled(chunk, style));
         }
     }
 
-    lines
+    Line {
+        spans,
+        style: line.style,
+        alignment: line.alignment,
+    }
 }
 
-fn render_inline_markdown(line: &str) -> Line<'static> {
-    let mut spans: Vec<Span<'static>> = Vec::new();
-    let mut current = String::new();
-    let chars: Vec<char> = line.chars().collect();
-    let mut i = 0;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    while i < chars.len() {
-        // Bold **text**
-        if i + 1 < chars.len() && chars[i] == '*' && chars[i + 1] == '*' {
-            if !current.is_empty() {
-                spans.push(Span::raw(current.clone()));
-                current.clear();
-            }
-            i += 2;
-            let mut bold_text = String::new();
-            while i + 1 < chars.len() && !(chars[i] == '*' && chars[i + 1] == '*') {
-                bold_text.push(chars[i]);
-                i += 1;
-            }
-            if i + 1 < chars.len() {
-                i += 2;
-            }
-            spans.push(Span::styled(
-                bold_text,
-                Style::default().add_modifier(Modifier::BOLD),
-            ));
-            continue;
-        }
+    fn lines(texts: &[&[&str]]) -> Vec<Line<'static>> {
+        texts
+            .iter()
+            .map(|spans| {
+                Line::from(
+                    spans
+                        .iter()
+                        .map(|s| Span::raw(s.to_string()))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .collect()
+    }
 
-        // Inline code `text`
-        if chars[i] == '`' {
-            if !current.is_empty() {
-                spans.push(Span::raw(current.clone()));
-                current.clear();
-            }
-            i += 1;
-            let mut code_text = String::new();
-            while i < chars.len() && chars[i] != '`' {
-                code_text.push(chars[i]);
-                i += 1;
-            }
-            if i < chars.len() {
-                i += 1;
-            }
-            spans.push(Span::styled(
-                code_text,
-                Style::default().fg(Color::Green).bg(Color::Rgb(30, 30, 30)),
-            ));
-            continue;
-        }
+    #[test]
+    fn matches_are_smart_case() {
+        let lines = lines(&[&["Rust and rust"], &["RUST"]]);
+        assert_eq!(
+            find_matches(&lines, "rust"),
+            [(0, 0, 4), (0, 9, 13), (1, 0, 4)]
+        );
+        assert_eq!(find_matches(&lines, "Rust"), [(0, 0, 4)]);
+        assert!(find_matches(&lines, "").is_empty());
+    }
 
-        // Italic *text* (single asterisk)
-        if chars[i] == '*' && (i + 1 >= chars.len() || chars[i + 1] != '*') {
-            if !current.is_empty() {
-                spans.push(Span::raw(current.clone()));
-                current.clear();
-            }
-            i += 1;
-            let mut italic_text = String::new();
-            while i < chars.len() && chars[i] != '*' {
-                italic_text.push(chars[i]);
-                i += 1;
-            }
-            if i < chars.len() {
-                i += 1;
-            }
-            spans.push(Span::styled(
-                italic_text,
-                Style::default().add_modifier(Modifier::ITALIC),
-            ));
-            continue;
-        }
+    #[test]
+    fn matches_do_not_overlap() {
+        assert_eq!(
+            find_matches(&lines(&[&["aaaa"]]), "aa"),
+            [(0, 0, 2), (0, 2, 4)]
+        );
+    }
 
-        current.push(chars[i]);
-        i += 1;
+    #[test]
+    fn matches_count_chars_not_bytes() {
+        let lines = lines(&[&["Ärger über Öl"]]);
+        assert_eq!(find_matches(&lines, "über"), [(0, 6, 10)]);
+        assert_eq!(find_matches(&lines, "öl"), [(0, 11, 13)]);
     }
 
-    if !current.is_empty() {
-        spans.push(Span::raw(current));
+    #[test]
+    fn folding_to_several_chars_keeps_offsets() {
+        // 'İ' lowercases to 'i' and a combining dot
+        let lines = lines(&[&["İstanbul is big"]]);
+        assert_eq!(find_matches(&lines, "stanbul"), [(0, 1, 8)]);
+        assert_eq!(find_matches(&lines, "big"), [(0, 12, 15)]);
+        assert_eq!(find_matches(&lines, "i\u{307}st"), [(0, 0, 3)]);
     }
 
-    Line::from(spans)
+    #[test]
+    fn highlights_across_span_boundaries() {
+        let line = Line::from(vec![
+            Span::styled("foo", Style::default().fg(Color::Red)),
+            Span::raw("bar baz"),
+        ]);
+        let matches = find_matches(std::slice::from_ref(&line), "obar");
+        assert_eq!(matches, [(0, 2, 6)]);
+
+        let split = split_spans(line, &[(2, 6, true)]);
+        let parts: Vec<(&str, Style)> = split
+            .spans
+            .iter()
+            .map(|span| (span.content.as_ref(), span.style))
+            .collect();
+        let current = Style::default().fg(Color::Black).bg(Color::LightRed);
+        assert_eq!(
+            parts,
+            [
+                ("fo", Style::default().fg(Color::Red)),
+                ("o", Style::default().fg(Color::Red).patch(current)),
+                ("bar", current),
+                (" baz", Style::default()),
+            ]
+        );
+    }
 }
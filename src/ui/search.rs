@@ -5,6 +5,7 @@ use ratatui::widgets::{Block, Borders, Paragraph};
 use ratatui::Frame;
 
 use crate::app::{App, Mode};
+use crate::fs::filter::MatchMode;
 
 pub fn render_search_bar(frame: &mut Frame, app: &App, area: Rect) {
     let is_searching = app.mode == Mode::Search;
@@ -21,7 +22,18 @@ pub fn render_search_bar(frame: &mut Frame, app: &App, area: Rect) {
         Span::styled("[/]", Style::default().fg(Color::DarkGray))
     };
 
-    let search_text = if app.search_query.is_empty() && !is_searching {
+    let search_text = if let Some(rs) = &app.reverse_search {
+        let failed = rs.match_index.is_none() && !rs.query.is_empty();
+        Span::styled(
+            format!(
+                " ({}reverse-i-search)`{}': {}",
+                if failed { "failed " } else { "" },
+                rs.query,
+                app.search_query
+            ),
+            Style::default().fg(Color::White),
+        )
+    } else if app.search_query.is_empty() && !is_searching {
         Span::styled(" Search...", Style::default().fg(Color::DarkGray))
     } else {
         Span::styled(
@@ -36,7 +48,26 @@ pub fn render_search_bar(frame: &mut Frame, app: &App, area: Rect) {
         Span::raw("")
     };
 
-    let result_count = if !app.search_query.is_empty() {
+    let mode_label = Span::styled(
+        format!("[{}] ", app.search_match_mode.label()),
+        Style::default().fg(if is_searching {
+            Color::Magenta
+        } else {
+            Color::DarkGray
+        }),
+    );
+
+    let result_count = if let Some(error) = &app.search_error {
+        Span::styled(
+            format!("  [invalid regex: {}]", error),
+            Style::default().fg(Color::Red),
+        )
+    } else if app.search_match_mode == MatchMode::Content
+        && !app.search_query.is_empty()
+        && app.is_indexing()
+    {
+        Span::styled("  [indexing…]", Style::default().fg(Color::Yellow))
+    } else if !app.search_query.is_empty() {
         let file_count = app
             .filtered_indices
             .iter()
@@ -56,6 +87,7 @@ pub fn render_search_bar(frame: &mut Frame, app: &App, area: Rect) {
     let help_hint = Span::styled("  [?] Help", Style::default().fg(Color::DarkGray));
 
     let line = Line::from(vec![
+        mode_label,
         search_label,
         search_text,
         cursor,
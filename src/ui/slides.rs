@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph, Wrap};
+use ratatui::Frame;
+
+use crate::app::{App, ImagePlacement};
+
+/// Widest a slide's text is rendered, so lines stay readable on wide screens.
+const MAX_SLIDE_WIDTH: u16 = 100;
+
+pub fn render_slides(frame: &mut Frame, app: &mut App) {
+    let area = frame.area();
+    frame.render_widget(Clear, area);
+
+    let notes = app
+        .slides
+        .get(app.slide_index)
+        .map(|slide| slide.notes.clone())
+        .unwrap_or_default();
+    let notes_height = if app.show_slide_notes {
+        let lines: usize = notes.iter().map(|note| note.lines().count() + 1).sum();
+        (lines as u16 + 1).clamp(3, (area.height / 3).max(3))
+    } else {
+        0
+    };
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Min(1),               // Slide
+            Constraint::Length(notes_height), // Speaker notes
+            Constraint::Length(1),            // Footer
+        ])
+        .split(area);
+
+    render_slide(frame, app, chunks[0]);
+    if app.show_slide_notes {
+        render_notes(frame, &notes, chunks[1]);
+    }
+    render_footer(frame, app, chunks[2]);
+}
+
+/// The slide centered in `area`, scrolled when it is taller than the screen.
+fn render_slide(frame: &mut Frame, app: &mut App, area: Rect) {
+    let width = area.width.saturating_sub(8).clamp(1, MAX_SLIDE_WIDTH);
+    let Some(document) = app.slide_document(width) else {
+        app.image_placements.clear();
+        return;
+    };
+
+    let block_width = document
+        .lines
+        .iter()
+        .map(Line::width)
+        .max()
+        .unwrap_or(0)
+        .clamp(1, width as usize) as u16;
+    let height = area.height as usize;
+    let rows = document.rows;
+    let (top, visible) = if rows <= height {
+        ((height - rows) / 2, rows)
+    } else {
+        (0, height)
+    };
+    let scroll = (app.slide_scroll as usize).min(rows.saturating_sub(height));
+    app.slide_scroll = scroll as u16;
+
+    let content = Rect::new(
+        area.x + (area.width.saturating_sub(block_width)) / 2,
+        area.y + top as u16,
+        block_width,
+        visible as u16,
+    );
+    let paragraph = Paragraph::new(document.lines.clone())
+        .wrap(Wrap { trim: false })
+        .scroll((scroll.min(u16::MAX as usize) as u16, 0));
+    frame.render_widget(paragraph, content);
+
+    // Graphics protocol images are drawn after the frame, only when wholly
+    // in view
+    app.image_placements = document
+        .images
+        .iter()
+        .filter_map(|image| {
+            let top = document.row_offsets[image.line].checked_sub(scroll)?;
+            (top + image.graphics.rows as usize <= visible).then(|| ImagePlacement {
+                x: content.x + image.column,
+                y: content.y + top as u16,
+                graphics: Arc::clone(&image.graphics),
+            })
+        })
+        .collect();
+}
+
+fn render_notes(frame: &mut Frame, notes: &[String], area: Rect) {
+    let lines: Vec<Line> = if notes.is_empty() {
+        vec![Line::from(Span::styled(
+            "No notes for this slide",
+            Style::default().fg(Color::DarkGray),
+        ))]
+    } else {
+        notes
+            .iter()
+            .enumerate()
+            .flat_map(|(i, note)| {
+                let gap = (i > 0).then(|| Line::from(""));
+                gap.into_iter()
+                    .chain(note.lines().map(|line| Line::from(line.to_string())))
+            })
+            .collect()
+    };
+
+    let block = Block::default()
+        .borders(Borders::TOP)
+        .border_style(Style::default().fg(Color::DarkGray))
+        .title(" Notes ");
+    let paragraph = Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false });
+    frame.render_widget(paragraph, area);
+}
+
+fn render_footer(frame: &mut Frame, app: &App, area: Rect) {
+    let key = Style::default().fg(Color::DarkGray);
+    let filename = app
+        .slides_path
+        .as_ref()
+        .and_then(|path| path.file_name())
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let title = if app.raw_view {
+        format!(" {} [raw]  ", filename)
+    } else {
+        format!(" {}  ", filename)
+    };
+
+    let hints = Paragraph::new(Line::from(vec![
+        Span::styled(title, Style::default().fg(Color::Cyan)),
+        match &app.status_message {
+            Some(message) => Span::styled(message.clone(), Style::default().fg(Color::Yellow)),
+            None => Span::styled("←/→ slides  s notes  i images  v raw  Esc exit", key),
+        },
+    ]));
+    frame.render_widget(hints, area);
+
+    let counter = Paragraph::new(Line::from(Span::styled(
+        format!("{} / {} ", app.slide_index + 1, app.slides.len()),
+        Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    )))
+    .alignment(Alignment::Right);
+    frame.render_widget(counter, area);
+}
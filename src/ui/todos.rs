@@ -0,0 +1,140 @@
+use ratatui::layout::Rect;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+use ratatui::Frame;
+
+use crate::app::{App, TodoSectionKey};
+use crate::markdown::todos::{TodoGroup, TodoKind};
+use crate::ui::display_path;
+
+pub fn render_todos(frame: &mut Frame, app: &App) {
+    let area = frame.area();
+
+    // Center the popup
+    let popup_width = (area.width * 4 / 5)
+        .max(40)
+        .min(area.width.saturating_sub(4));
+    let popup_height = (area.height * 4 / 5)
+        .max(10)
+        .min(area.height.saturating_sub(4));
+    let popup_x = (area.width.saturating_sub(popup_width)) / 2;
+    let popup_y = (area.height.saturating_sub(popup_height)) / 2;
+    let popup_area = Rect::new(popup_x, popup_y, popup_width, popup_height);
+
+    frame.render_widget(Clear, popup_area);
+
+    let count = app.todo_count();
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(Style::default().fg(Color::Cyan))
+        .title(format!(
+            " TODOs ({} {}/{}) ",
+            app.todo_filter.label(),
+            count,
+            app.todo_index.len()
+        ));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    let key = Style::default().fg(Color::Cyan);
+    let hint = Style::default().fg(Color::DarkGray);
+    let controls = Paragraph::new(Line::from(vec![
+        Span::styled("g", key),
+        Span::styled(format!(" by {}  ", app.todo_group.label()), hint),
+        Span::styled("f", key),
+        Span::styled(format!(" show {}  ", app.todo_filter.label()), hint),
+        Span::styled("Enter", key),
+        Span::styled(" open  ", hint),
+        Span::styled("Esc", key),
+        Span::styled(" close", hint),
+    ]));
+    frame.render_widget(controls, Rect::new(inner.x, inner.y, inner.width, 1));
+
+    let list_area = Rect::new(
+        inner.x,
+        inner.y + 2,
+        inner.width,
+        inner.height.saturating_sub(2),
+    );
+
+    if count == 0 {
+        let empty = Paragraph::new(Line::from(Span::styled(
+            format!("No {} items", app.todo_filter.label()),
+            hint,
+        )));
+        frame.render_widget(empty, list_area);
+        return;
+    }
+
+    // Section headings take up rows too, so the cursor is mapped to its row
+    let mut items: Vec<ListItem> = Vec::new();
+    let mut selected_row = 0;
+    let mut item_number = 0;
+    for section in &app.todo_sections {
+        let title = match &section.key {
+            TodoSectionKey::Path(path) => display_path(path),
+            TodoSectionKey::Owner(owner) => owner.clone(),
+            TodoSectionKey::NoOwner => "(no owner)".to_string(),
+        };
+        items.push(ListItem::new(Line::from(vec![
+            Span::styled(
+                title,
+                Style::default()
+                    .fg(Color::Yellow)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(format!(" ({})", section.items.len()), hint),
+        ])));
+
+        for &idx in &section.items {
+            let entry = &app.todo_index[idx];
+            let marker = match entry.item.kind {
+                TodoKind::Task { checked: false } => Span::styled("☐", key),
+                TodoKind::Task { checked: true } => {
+                    Span::styled("☑", Style::default().fg(Color::Green))
+                }
+                TodoKind::Todo => Span::styled("TODO", Style::default().fg(Color::Yellow)),
+                TodoKind::Fixme => Span::styled("FIXME", Style::default().fg(Color::Red)),
+            };
+            let location = match app.todo_group {
+                TodoGroup::File => format!("  :{}", entry.item.line + 1),
+                TodoGroup::Directory => format!(
+                    "  {}:{}",
+                    entry.path.file_name().unwrap_or_default().to_string_lossy(),
+                    entry.item.line + 1
+                ),
+                TodoGroup::Owner => {
+                    format!("  {}:{}", display_path(&entry.path), entry.item.line + 1)
+                }
+            };
+            let text_style = if entry.item.kind.is_open() {
+                Style::default().fg(Color::White)
+            } else {
+                Style::default().fg(Color::DarkGray)
+            };
+
+            if item_number == app.todo_selected {
+                selected_row = items.len();
+            }
+            item_number += 1;
+            items.push(ListItem::new(Line::from(vec![
+                Span::raw("  "),
+                marker,
+                Span::raw(" "),
+                Span::styled(entry.item.text.clone(), text_style),
+                Span::styled(location, hint),
+            ])));
+        }
+    }
+
+    let list = List::new(items).highlight_style(
+        Style::default()
+            .bg(Color::Rgb(40, 40, 60))
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let mut state = ListState::default();
+    state.select(Some(selected_row));
+    frame.render_stateful_widget(list, list_area, &mut state);
+}